@@ -0,0 +1,542 @@
+//! Checks of the API URL that need nothing but the URL: its scheme, host and path.
+
+use url::Url;
+
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticCode, Report},
+    fix::Fix,
+    heartbeat,
+    host::WakaHost,
+};
+
+/// API URL of current Hackatime.
+const HACKATIME_API_URL: &str = "https://hackatime.hackclub.com/api/hackatime/v1";
+
+/// API URL of Wakatime.
+const WAKATIME_API_URL: &str = "https://api.wakatime.com/api/v1";
+
+/// Hosts of the Wakatime website, which are not the API.
+const WAKATIME_WEBSITE_HOSTS: &[&str] = &["wakatime.com", "www.wakatime.com"];
+
+/// Suffixes of the API paths custom servers conventionally use.
+const CUSTOM_API_PATHS: &[&str] = &[
+    "/api",
+    "/api/v1",
+    "/api/hackatime/v1",
+    "/api/compat/wakatime/v1",
+];
+
+/// Options controlling how the API URL is judged.
+#[derive(Clone, Debug, Default)]
+pub struct UrlOptions {
+    /// Assume the user isn't trying to use Hackatime.
+    pub no_warn_default_waka: bool,
+    /// Assume the user is trying to use a custom server.
+    pub custom_server: bool,
+    /// Hosts of trusted custom servers.
+    pub known_hosts: Vec<String>,
+    /// Kind of server to treat the host as, instead of classifying it.
+    pub assume_host: Option<WakaHost>,
+    /// Do not check the API path, for reverse proxies that rewrite it.
+    pub no_path_check: bool,
+}
+
+/// Check the scheme, host and path of `url`, without any I/O.
+pub fn analyze_url(url: &Url, opts: &UrlOptions) -> Report {
+    let mut report = Report::new();
+    check_api_url(url, opts, &mut report);
+    report
+}
+
+/// Same as [`analyze_url`], but pushing to `report`. Returns the kind of host, if `url` has one,
+/// and whether it uses HTTPS.
+pub(crate) fn check_api_url(
+    url: &Url,
+    opts: &UrlOptions,
+    report: &mut Report,
+) -> (Option<WakaHost>, bool) {
+    let https = check_scheme(url, report);
+    let host = check_host(url, opts, report);
+    if let Some(host) = host
+        && !check_website(url, report)
+    {
+        // The old Hackatime migration is about the host, not the path, so it is always checked.
+        if !opts.no_path_check || host == WakaHost::OldHackatime {
+            check_path(url, host, opts, report);
+        }
+        check_endpoint(url, report);
+    }
+    (host, https)
+}
+
+/// Check `url` is not the address of the Wakatime or Hackatime website rather than the API.
+/// Returns whether it is.
+fn check_website(url: &Url, report: &mut Report) -> bool {
+    let host = url.host_str().unwrap_or_default();
+    let api_url = if WAKATIME_WEBSITE_HOSTS.contains(&host) {
+        WAKATIME_API_URL
+    } else if host == "hackatime.hackclub.com" && url.path().trim_matches('/').is_empty() {
+        HACKATIME_API_URL
+    } else {
+        return false;
+    };
+    report.push(
+        Diagnostic::error(
+            DiagnosticCode::ApiUrlWebsite,
+            format!("api_url points at the website, not the API; use {api_url}"),
+        )
+        .with_fix(Fix::SetApiUrl(api_url)),
+    );
+    true
+}
+
+/// Classify the host of `url`.
+///
+/// Hosts in `opts.known_hosts` are trusted custom servers, and `opts.assume_host` replaces the
+/// classification altogether.
+fn check_host(url: &Url, opts: &UrlOptions, report: &mut Report) -> Option<WakaHost> {
+    let Some(name) = url.host_str().filter(|v| !v.is_empty()) else {
+        report.push(Diagnostic::error(
+            DiagnosticCode::HostNull,
+            format!("Wakatime API URL \"{url}\" has no host"),
+        ));
+        return None;
+    };
+
+    if let Some(host) = opts.assume_host {
+        let treated_as = match host {
+            WakaHost::Hackatime | WakaHost::OldHackatime => "Hackatime",
+            WakaHost::Wakatime => "Wakatime",
+            WakaHost::Custom => "a custom server",
+        };
+        report.push(Diagnostic::info(
+            DiagnosticCode::HostAssumed,
+            format!("Treating host as {treated_as} per --assume-host"),
+        ));
+        return Some(host);
+    }
+
+    let host = match name {
+        "hackatime.hackclub.com" => {
+            report.push(Diagnostic::ok(
+                DiagnosticCode::HostHackatime,
+                "Wakatime API host is Hackatime host",
+            ));
+            WakaHost::Hackatime
+        }
+        "waka.hackclub.com" => {
+            report.push(Diagnostic::warning(
+                DiagnosticCode::HostOldHackatime,
+                "Wakatime API host is old Hackclub Wakatime host",
+            ));
+            WakaHost::OldHackatime
+        }
+        // The website is reported by check_website, but its key rules are Wakatime's.
+        name if WAKATIME_WEBSITE_HOSTS.contains(&name) => WakaHost::Wakatime,
+        "api.wakatime.com" => {
+            if opts.no_warn_default_waka {
+                report.push(Diagnostic::ok(
+                    DiagnosticCode::HostWakatimeDefault,
+                    "Wakatime API host is default Wakatime host",
+                ));
+            } else {
+                report.push(Diagnostic::warning(
+                    DiagnosticCode::HostWakatimeDefault,
+                    "Wakatime API host is default Wakatime host (psst- disable this warning with \
+                     --no-warn-default-waka)",
+                ));
+            }
+            WakaHost::Wakatime
+        }
+        other => {
+            if opts.known_hosts.iter().any(|v| v == other) {
+                report.push(Diagnostic::ok(
+                    DiagnosticCode::HostKnownCustom,
+                    "API host is a known custom server",
+                ));
+            } else if opts.custom_server {
+                report.push(Diagnostic::ok(
+                    DiagnosticCode::HostCustom,
+                    "Wakatime API host is custom server host",
+                ));
+            } else {
+                report.push(Diagnostic::warning(
+                    DiagnosticCode::HostCustom,
+                    "Wakatime API host is custom server host or invalid host (psst- disable this \
+                     warning with --custom-server)",
+                ));
+            }
+            WakaHost::Custom
+        }
+    };
+    Some(host)
+}
+
+/// Check the API path is correct for `host`.
+fn check_path(url: &Url, host: WakaHost, opts: &UrlOptions, report: &mut Report) {
+    match host {
+        WakaHost::Hackatime => {
+            if url.path().trim_end_matches('/') != "/api/hackatime/v1" {
+                report.push(
+                    Diagnostic::error(
+                        DiagnosticCode::PathWrong,
+                        format!(
+                            "Hackatime API path should be \"/api/hackatime/v1\", not \"{}\"",
+                            url.path()
+                        ),
+                    )
+                    .with_fix(Fix::SetApiPath("/api/hackatime/v1".to_string())),
+                );
+            } else {
+                report.push(Diagnostic::ok(
+                    DiagnosticCode::PathOk,
+                    "Hackatime API path is correct.",
+                ));
+            }
+        }
+        WakaHost::OldHackatime => {
+            report.push(
+                Diagnostic::warning(
+                    DiagnosticCode::OldHackatimeMigrate,
+                    format!(
+                        "waka.hackclub.com is deprecated; set api_url = {} to use current Hackatime",
+                        HACKATIME_API_URL
+                    ),
+                )
+                .with_fix(Fix::SetApiUrl(HACKATIME_API_URL)),
+            );
+        }
+        WakaHost::Wakatime => {
+            if url.path().trim_end_matches('/') != "/api/v1" {
+                report.push(
+                    Diagnostic::error(
+                        DiagnosticCode::PathWrong,
+                        format!(
+                            "Wakatime API path should be \"/api/v1\", not \"{}\"",
+                            url.path()
+                        ),
+                    )
+                    .with_fix(Fix::SetApiPath("/api/v1".to_string())),
+                );
+            } else {
+                report.push(Diagnostic::ok(
+                    DiagnosticCode::PathOk,
+                    "Wakatime API path is correct.",
+                ));
+            }
+        }
+        WakaHost::Custom => {
+            let path = url.path().trim_end_matches('/');
+            if !opts.custom_server && !CUSTOM_API_PATHS.iter().any(|v| path.ends_with(v)) {
+                report.push(Diagnostic::warning(
+                    DiagnosticCode::PathUnusual,
+                    format!(
+                        "Custom server API path \"{}\" doesn't look like an API path; it usually ends in \"/api/v1\" (psst- disable this warning with --custom-server)",
+                        url.path()
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+/// Check for path mistakes that break building endpoint URLs from `url`, and show the heartbeat
+/// endpoint.
+fn check_endpoint(url: &Url, report: &mut Report) {
+    let path = url.path();
+    if path.trim_end_matches('/').ends_with("/heartbeats")
+        || path.trim_end_matches('/').ends_with("/heartbeats.bulk")
+    {
+        report.push(Diagnostic::warning(
+            DiagnosticCode::ApiUrlEndpoint,
+            "api_url includes the heartbeats endpoint; it should be the API base URL, e.g. ending in /api/v1",
+        ));
+    }
+    if path.contains("//") {
+        report.push(Diagnostic::warning(
+            DiagnosticCode::ApiUrlEmptySegment,
+            format!("api_url path \"{path}\" contains \"//\", which some servers reject"),
+        ));
+    } else if path.len() > 1 && path.ends_with('/') {
+        report.push(Diagnostic::warning(
+            DiagnosticCode::ApiUrlTrailingSlash,
+            "api_url ends with a slash; wakatime handles this, but some other clients do not, so remove it to be safe",
+        ));
+    }
+    report.push(Diagnostic::info(
+        DiagnosticCode::Endpoint,
+        format!(
+            "Heartbeats will be sent to {}",
+            heartbeat::endpoint(url, "users/current/heartbeats")
+        ),
+    ));
+}
+
+/// Check `url` uses HTTPS. Returns whether it does.
+fn check_scheme(url: &Url, report: &mut Report) -> bool {
+    if url.scheme() != "https" {
+        if url.scheme() == "http" {
+            report.push(
+                Diagnostic::error(
+                    DiagnosticCode::SchemeHttp,
+                    format!(
+                        "Wakatime API URL is unsecured HTTP; use {} instead",
+                        url.as_str().replacen("http://", "https://", 1)
+                    ),
+                )
+                .with_fix(Fix::UseHttps),
+            );
+        } else {
+            report.push(Diagnostic::error(
+                DiagnosticCode::SchemeUnknown,
+                format!("Wakatime API URL has unknown scheme \"{}\"", url.scheme()),
+            ));
+        }
+        false
+    } else {
+        report.push(Diagnostic::ok(
+            DiagnosticCode::SchemeHttps,
+            "Wakatime API URL is HTTPS",
+        ));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn analyzes_urls() {
+        let cases: &[(&str, UrlOptions, &[&str])] = &[
+            (
+                "https://hackatime.hackclub.com/api/hackatime/v1",
+                UrlOptions::default(),
+                &["scheme_https", "host_hackatime", "path_ok", "endpoint"],
+            ),
+            (
+                "https://hackatime.hackclub.com/api/v1",
+                UrlOptions::default(),
+                &["scheme_https", "host_hackatime", "path_wrong", "endpoint"],
+            ),
+            (
+                "https://waka.hackclub.com/api/v1",
+                UrlOptions::default(),
+                &[
+                    "scheme_https",
+                    "host_old_hackatime",
+                    "old_hackatime_migrate",
+                    "endpoint",
+                ],
+            ),
+            (
+                "https://api.wakatime.com/api/v1",
+                UrlOptions::default(),
+                &[
+                    "scheme_https",
+                    "host_wakatime_default",
+                    "path_ok",
+                    "endpoint",
+                ],
+            ),
+            (
+                "https://api.wakatime.com/v1",
+                UrlOptions::default(),
+                &[
+                    "scheme_https",
+                    "host_wakatime_default",
+                    "path_wrong",
+                    "endpoint",
+                ],
+            ),
+            (
+                "https://my.server/api/v1",
+                UrlOptions::default(),
+                &["scheme_https", "host_custom", "endpoint"],
+            ),
+            (
+                "https://my.server/api/v1",
+                UrlOptions {
+                    known_hosts: vec!["my.server".to_string()],
+                    ..Default::default()
+                },
+                &["scheme_https", "host_known_custom", "endpoint"],
+            ),
+            (
+                "http://hackatime.hackclub.com/api/hackatime/v1",
+                UrlOptions::default(),
+                &["scheme_http", "host_hackatime", "path_ok", "endpoint"],
+            ),
+            (
+                "ftp://my.server/api/v1",
+                UrlOptions::default(),
+                &["scheme_unknown", "host_custom", "endpoint"],
+            ),
+            (
+                "https://192.168.1.5:3000/api/hackatime/v1",
+                UrlOptions {
+                    assume_host: Some(WakaHost::Hackatime),
+                    ..Default::default()
+                },
+                &["scheme_https", "host_assumed", "path_ok", "endpoint"],
+            ),
+            (
+                "https://tunnel.example.com/v1",
+                UrlOptions {
+                    assume_host: Some(WakaHost::Wakatime),
+                    ..Default::default()
+                },
+                &["scheme_https", "host_assumed", "path_wrong", "endpoint"],
+            ),
+            (
+                "https://hackatime.hackclub.com/proxied",
+                UrlOptions {
+                    no_path_check: true,
+                    ..Default::default()
+                },
+                &["scheme_https", "host_hackatime", "endpoint"],
+            ),
+            (
+                "https://my.server/",
+                UrlOptions {
+                    custom_server: true,
+                    no_path_check: true,
+                    ..Default::default()
+                },
+                &["scheme_https", "host_custom", "endpoint"],
+            ),
+            (
+                "https://wakatime.com",
+                UrlOptions::default(),
+                &["scheme_https", "api_url_website"],
+            ),
+            (
+                "https://www.wakatime.com/dashboard",
+                UrlOptions::default(),
+                &["scheme_https", "api_url_website"],
+            ),
+            (
+                "https://hackatime.hackclub.com/",
+                UrlOptions::default(),
+                &["scheme_https", "host_hackatime", "api_url_website"],
+            ),
+        ];
+        for (url, opts, expected) in cases {
+            let report = analyze_url(&Url::parse(url).unwrap(), opts);
+            let codes = report.iter().map(|d| d.code.as_str()).collect::<Vec<_>>();
+            assert_eq!(codes, *expected, "{url}");
+        }
+    }
+
+    #[test]
+    fn default_wakatime_warning() {
+        let level = |no_warn_default_waka| {
+            let opts = UrlOptions {
+                no_warn_default_waka,
+                ..Default::default()
+            };
+            let report = analyze_url(
+                &Url::parse("https://api.wakatime.com/api/v1").unwrap(),
+                &opts,
+            );
+            report
+                .into_iter()
+                .find(|d| d.code == DiagnosticCode::HostWakatimeDefault)
+                .unwrap()
+                .level
+        };
+        assert_eq!(level(false), crate::diagnostic::Level::Warning);
+        assert_eq!(level(true), crate::diagnostic::Level::Ok);
+    }
+
+    #[test]
+    fn hostless_urls() {
+        for url in [
+            "mailto:foo@example.com",
+            "file:///etc/hosts",
+            "data:text/plain,hi",
+            "unix:/run/waka.sock",
+        ] {
+            let mut report = Report::new();
+            let host = check_host(
+                &Url::parse(url).unwrap(),
+                &UrlOptions::default(),
+                &mut report,
+            );
+            assert_eq!(host, None, "{url}");
+            assert_eq!(
+                report.iter().map(|d| d.code.as_str()).collect::<Vec<_>>(),
+                ["host_null"],
+                "{url}"
+            );
+        }
+    }
+
+    #[test]
+    fn endpoint_pitfalls() {
+        let codes = |url: &str| {
+            let mut report = Report::new();
+            check_endpoint(&Url::parse(url).unwrap(), &mut report);
+            report.iter().map(|d| d.code.as_str()).collect::<Vec<_>>()
+        };
+        assert_eq!(codes("https://api.wakatime.com/api/v1"), ["endpoint"]);
+        assert_eq!(
+            codes("https://api.wakatime.com/api/v1/"),
+            ["api_url_trailing_slash", "endpoint"]
+        );
+        assert_eq!(
+            codes("https://my.server//api/v1"),
+            ["api_url_empty_segment", "endpoint"]
+        );
+        assert_eq!(
+            codes("https://api.wakatime.com/api/v1/users/current/heartbeats"),
+            ["api_url_endpoint", "endpoint"]
+        );
+    }
+
+    #[test]
+    fn custom_paths() {
+        let codes = |url: &str, custom_server: bool| {
+            let opts = UrlOptions {
+                custom_server,
+                ..Default::default()
+            };
+            let mut report = Report::new();
+            check_path(
+                &Url::parse(url).unwrap(),
+                WakaHost::Custom,
+                &opts,
+                &mut report,
+            );
+            report.iter().map(|d| d.code.as_str()).collect::<Vec<_>>()
+        };
+        assert_eq!(codes("https://my.server/", false), ["path_unusual"]);
+        assert!(codes("https://my.server/", true).is_empty());
+        assert!(codes("https://my.server/api/v1/", false).is_empty());
+        assert!(codes("https://my.server/wakapi/api", false).is_empty());
+    }
+
+    #[test]
+    fn trailing_slash_paths() {
+        for (url, host) in [
+            ("https://api.wakatime.com/api/v1/", WakaHost::Wakatime),
+            (
+                "https://hackatime.hackclub.com/api/hackatime/v1/",
+                WakaHost::Hackatime,
+            ),
+        ] {
+            let mut report = Report::new();
+            check_path(
+                &Url::parse(url).unwrap(),
+                host,
+                &UrlOptions::default(),
+                &mut report,
+            );
+            assert_eq!(
+                report.iter().map(|d| d.code.as_str()).collect::<Vec<_>>(),
+                ["path_ok"],
+                "{url}"
+            );
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Probing which heartbeat endpoints a self-hosted server implements.
+
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use url::Url;
+
+use crate::{
+    client,
+    diagnostic::{Diagnostic, DiagnosticCode, Report},
+    heartbeat::endpoint,
+};
+
+/// Diagnostic codes the endpoint check can report.
+pub const CODES: &[DiagnosticCode] = &[
+    DiagnosticCode::HeartbeatEndpoints,
+    DiagnosticCode::HeartbeatEndpointsMissing,
+    DiagnosticCode::HeartbeatSingleMissing,
+    DiagnosticCode::HeartbeatBulkMissing,
+];
+
+/// Path of the endpoint taking one heartbeat, under the API URL.
+pub const SINGLE_PATH: &str = "users/current/heartbeats";
+
+/// Path of the endpoint taking a batch of heartbeats, under the API URL. wakatime-cli sends
+/// every heartbeat here.
+pub const BULK_PATH: &str = "users/current/heartbeats.bulk";
+
+/// Whether the API at `url` has the endpoint `path`, found by posting an empty batch of
+/// heartbeats, which records nothing.
+///
+/// Returns the status if the endpoint is missing, or `None` if the request failed.
+async fn probe(
+    url: &Url,
+    path: &str,
+    api_key: &str,
+    client: &reqwest::Client,
+    timeout: Duration,
+    verbose: bool,
+) -> Option<Result<(), StatusCode>> {
+    let response = client::send(
+        client
+            .post(endpoint(url, path))
+            .bearer_auth(api_key)
+            .body("[]")
+            .header("Content-Type", "application/json")
+            .timeout(timeout),
+        verbose,
+    )
+    .await
+    .ok()?;
+    // Anything else, even a rejection of the empty batch, came from the endpoint itself.
+    match response.status() {
+        status @ (StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED) => Some(Err(status)),
+        _ => Some(Ok(())),
+    }
+}
+
+/// Report which of the single and bulk heartbeat endpoints the API at `url` supports.
+///
+/// Nothing is reported if the server cannot be reached, as the other network checks explain why.
+pub async fn check_endpoints(
+    url: &Url,
+    api_key: &str,
+    client: &reqwest::Client,
+    timeout: Duration,
+    verbose: bool,
+    report: &mut Report,
+) {
+    let (single, bulk) = tokio::join!(
+        probe(url, SINGLE_PATH, api_key, client, timeout, verbose),
+        probe(url, BULK_PATH, api_key, client, timeout, verbose),
+    );
+    let (Some(single), Some(bulk)) = (single, bulk) else {
+        return;
+    };
+    report.push(match (single, bulk) {
+        (Ok(()), Ok(())) => Diagnostic::ok(
+            DiagnosticCode::HeartbeatEndpoints,
+            "Server supports both the heartbeats and heartbeats.bulk endpoints",
+        ),
+        (Ok(()), Err(status)) => Diagnostic::warning(
+            DiagnosticCode::HeartbeatBulkMissing,
+            format!(
+                "Server does not support heartbeats.bulk ({status}), which wakatime-cli sends heartbeats to"
+            ),
+        ),
+        (Err(status), Ok(())) => Diagnostic::info(
+            DiagnosticCode::HeartbeatSingleMissing,
+            format!(
+                "Server does not support the single heartbeats endpoint ({status}), only heartbeats.bulk"
+            ),
+        ),
+        (Err(status), Err(_)) => Diagnostic::error(
+            DiagnosticCode::HeartbeatEndpointsMissing,
+            format!(
+                "Server supports neither the heartbeats nor the heartbeats.bulk endpoint ({status})"
+            ),
+        ),
+    });
+}
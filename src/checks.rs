@@ -0,0 +1,796 @@
+//! The checks run against a parsed config.
+
+use std::{path::PathBuf, time::Duration};
+
+use url::Url;
+
+use crate::{
+    api_url::{self, UrlOptions},
+    bulk,
+    client::{self, IpVersion, TlsVersion},
+    config::{self, WakaConfig},
+    diagnostic::{Diagnostic, DiagnosticCode, Level, Report},
+    dns, dry_run, explain,
+    fix::Fix,
+    heartbeat,
+    host::WakaHost,
+    internal, known_hosts, paths, permissions, projectmap, queue, redact, settings, tls, update,
+    update::Version,
+    user::{self, UserCheck},
+    vault, wakapi, wakatime_cli,
+};
+
+/// Options controlling how the checks behave.
+#[derive(Clone, Debug, Default)]
+pub struct CheckOptions {
+    /// Assume the user isn't trying to use Hackatime.
+    pub no_warn_default_waka: bool,
+    /// Assume the user is trying to use a custom server.
+    pub custom_server: bool,
+    /// Kind of server to treat the API host as, instead of classifying it.
+    pub assume_host: Option<WakaHost>,
+    /// Do not check the API path.
+    pub no_path_check: bool,
+    /// Do not use the network at all.
+    pub offline: bool,
+    /// List the network requests the checks would make instead of making them.
+    pub dry_run: bool,
+    /// Send a real test heartbeat, instead of only probing the API.
+    pub send_real_heartbeat: bool,
+    /// Entity of the test heartbeat instead of [`heartbeat::DEFAULT_ENTITY`].
+    pub heartbeat_entity: Option<String>,
+    /// Language of the test heartbeat instead of [`heartbeat::DEFAULT_LANGUAGE`].
+    pub heartbeat_language: Option<String>,
+    /// Project of the test heartbeat, which has none by default.
+    pub heartbeat_project: Option<String>,
+    /// Run extra checks and log the requests that are sent.
+    pub verbose: bool,
+    /// Do not remove the test heartbeat after sending it.
+    pub no_cleanup: bool,
+    /// Heartbeat timeout in seconds, overriding the config.
+    pub timeout: Option<u64>,
+    /// Timeout in seconds for establishing a connection, separate from `timeout`.
+    pub connect_timeout: Option<u64>,
+    /// Seconds all network checks together may take before the rest are aborted.
+    pub deadline: Option<u64>,
+    /// IP address family to connect with.
+    pub ip_version: IpVersion,
+    /// Oldest TLS version to connect with.
+    pub min_tls: Option<TlsVersion>,
+    /// Number of times to retry a failed heartbeat.
+    pub retries: u32,
+    /// Proxy URL, overriding the config.
+    pub proxy: Option<String>,
+    /// Do not look for the wakatime CLI.
+    pub skip_cli_check: bool,
+    /// Oldest wakatime CLI version not warned about.
+    pub min_cli_version: Option<Version>,
+    /// Do not warn about inconsistent privacy settings.
+    pub no_privacy_check: bool,
+    /// API key, overriding `api_key` and `api_key_vault_cmd` in the config.
+    pub api_key: Option<String>,
+    /// API URL, overriding `api_url` in the config.
+    pub api_url: Option<String>,
+    /// File listing trusted custom hosts, one per line.
+    pub known_hosts_file: Option<PathBuf>,
+    /// Add a custom host to `known_hosts_file`.
+    pub remember_host: bool,
+    /// User-Agent of requests, instead of one mimicking wakatime-cli.
+    pub user_agent: Option<String>,
+    /// Do not check wakatime-cli's internal state file.
+    pub no_internal_check: bool,
+    /// Expand environment variables in config values.
+    pub expand_env: bool,
+    /// Check crates.io for a newer wakadoctor.
+    pub check_update: bool,
+    /// Only report these diagnostic codes, selected as with [`explain::selects`], and skip
+    /// probes reporting none of them.
+    pub only: Vec<String>,
+    /// Do not report these diagnostic codes, and skip probes reporting only them.
+    pub skip: Vec<String>,
+}
+
+impl CheckOptions {
+    /// Whether [`only`](Self::only) and [`skip`](Self::skip) keep `code` in the report.
+    pub fn selects(&self, code: DiagnosticCode) -> bool {
+        let code = code.as_str();
+        (self.only.is_empty() || self.only.iter().any(|v| explain::selects(v, code)))
+            && !self.skip.iter().any(|v| explain::selects(v, code))
+    }
+
+    /// Whether a probe reporting `codes` is worth running.
+    fn selects_any(&self, codes: &[DiagnosticCode]) -> bool {
+        codes.iter().any(|&code| self.selects(code))
+    }
+}
+
+/// Run `future` until `deadline`, a number of seconds and the instant it is reached, if any.
+///
+/// Returns `None` if the deadline passes first.
+async fn until<T>(
+    deadline: Option<(u64, tokio::time::Instant)>,
+    future: impl Future<Output = T>,
+) -> Option<T> {
+    match deadline {
+        Some((_, deadline)) => tokio::time::timeout_at(deadline, future).await.ok(),
+        None => Some(future.await),
+    }
+}
+
+/// The warning that network checks were aborted after `seconds`.
+fn deadline_reached(seconds: u64) -> Diagnostic {
+    Diagnostic::warning(
+        DiagnosticCode::Deadline,
+        format!("Network checks aborted after reaching the {seconds}s deadline"),
+    )
+}
+
+/// Like [`until`], but also warns in `report` if the deadline passes first.
+async fn within<T>(
+    deadline: Option<(u64, tokio::time::Instant)>,
+    future: impl Future<Output = T>,
+    report: &mut Report,
+) -> Option<T> {
+    let result = until(deadline, future).await;
+    if result.is_none()
+        && let Some((seconds, _)) = deadline
+    {
+        report.push(deadline_reached(seconds));
+    }
+    result
+}
+
+/// Run all checks against `config`.
+///
+/// Every check that can run is run; checks whose prerequisites failed are skipped.
+pub async fn run_checks(config: &WakaConfig, opts: &CheckOptions) -> Report {
+    let mut report = Report::new();
+
+    let expanded;
+    let config = if opts.expand_env {
+        let mut v = config.clone();
+        config::expand_env(&mut v.settings, &mut report);
+        expanded = v;
+        &expanded
+    } else {
+        config
+    };
+
+    let url = check_url(config, opts, &mut report);
+    let url_opts = UrlOptions {
+        no_warn_default_waka: opts.no_warn_default_waka,
+        custom_server: opts.custom_server,
+        known_hosts: load_known_hosts(opts, &mut report),
+        assume_host: opts.assume_host,
+        no_path_check: opts.no_path_check,
+    };
+    let (host, https) = match &url {
+        Some(url) => api_url::check_api_url(url, &url_opts, &mut report),
+        None => (None, false),
+    };
+    if opts.remember_host
+        && host == Some(WakaHost::Custom)
+        && let Some(name) = url.as_ref().and_then(|url| url.host_str())
+        && !url_opts.known_hosts.iter().any(|v| v == name)
+    {
+        remember_host(name, opts, &mut report);
+    }
+
+    let api_key = resolve_api_key(config, opts, &mut report).await;
+    if let Some(api_key) = &api_key {
+        check_key_format(api_key, host, &mut report);
+    }
+
+    settings::check_settings(&config.settings, opts, &mut report);
+    projectmap::check_projectmap(&config.projectmap, &mut report);
+    if let Some(path) = &config.path {
+        permissions::check_permissions(path, &mut report);
+        if !opts.no_internal_check {
+            internal::check_internal(path, &mut report);
+        }
+        queue::check_queue(path, &mut report);
+    }
+
+    let mut client_report = Report::new();
+    let client = client::build_client(&config.settings, opts, &mut client_report);
+    let timeout = Duration::from_secs(
+        opts.timeout
+            .or_else(|| {
+                config
+                    .settings
+                    .timeout
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|v| *v > 0)
+            })
+            .unwrap_or(heartbeat::DEFAULT_TIMEOUT),
+    );
+
+    // Every step below that touches the network checks this, so --offline and --dry-run skip
+    // all of them.
+    let network = !opts.offline && !opts.dry_run;
+    if opts.offline {
+        report.push(Diagnostic::info(
+            DiagnosticCode::Offline,
+            "Offline mode: skipping all network checks",
+        ));
+    }
+
+    // With a proxy, the proxy may resolve the host for us, so a local failure means nothing.
+    let proxied = opts.proxy.is_some() || !config.settings.proxy.is_empty();
+    if opts.dry_run {
+        dry_run::plan_requests(
+            url.as_ref(),
+            host,
+            https,
+            api_key.as_deref(),
+            proxied,
+            opts,
+            &mut report,
+        );
+    }
+    // Probes reporting no selected code are not run at all.
+    let want_cli = !opts.skip_cli_check && opts.selects_any(wakatime_cli::CODES);
+    let want_wakapi = opts.selects_any(wakapi::CODES);
+    let want_heartbeat = opts.selects_any(user::CODES)
+        || opts.selects_any(heartbeat::CODES)
+        || opts.selects_any(&[
+            DiagnosticCode::HeartbeatProbeOnly,
+            DiagnosticCode::HeartbeatSkipped,
+        ]);
+    let want_tls = opts.selects_any(tls::CODES);
+    let want_update = opts.check_update && opts.selects_any(update::CODES);
+    let want_endpoints = opts.selects_any(bulk::CODES);
+    let want_dns =
+        opts.selects_any(dns::CODES) || want_wakapi || want_heartbeat || want_tls || want_endpoints;
+
+    let deadline = opts
+        .deadline
+        .map(|v| (v, tokio::time::Instant::now() + Duration::from_secs(v)));
+    let resolved = match &url {
+        Some(url) if network && !proxied && want_dns => {
+            let mut dns_report = Report::new();
+            let resolved = within(
+                deadline,
+                dns::check_dns(url, timeout, opts.ip_version, &mut dns_report),
+                &mut report,
+            )
+            .await;
+            report.extend(dns_report);
+            resolved
+        }
+        _ => Some(true),
+    };
+    // Past the deadline, the other network checks are not even started.
+    let network = network && resolved.is_some();
+    let resolved = resolved.unwrap_or(false);
+
+    // The probes below are independent, so run them concurrently. Each collects its own
+    // diagnostics, which are appended in a fixed order afterwards.
+    let cli = async {
+        let mut report = Report::new();
+        if want_cli {
+            wakatime_cli::check_cli(
+                opts.min_cli_version
+                    .as_ref()
+                    .unwrap_or(&wakatime_cli::MIN_CLI_VERSION),
+                &mut report,
+            )
+            .await;
+        }
+        report
+    };
+    let wakapi = async {
+        let mut report = Report::new();
+        if network
+            && want_wakapi
+            && resolved
+            && host == Some(WakaHost::Custom)
+            && let (Some(url), Some(client)) = (&url, &client)
+        {
+            wakapi::detect(url, client, timeout, opts.verbose, &mut report).await;
+        }
+        report
+    };
+    let heartbeat = async {
+        let mut report = Report::new();
+        if !network || !want_heartbeat {
+            // Already reported once for all network checks, or not selected.
+        } else if let (Some(url), Some(host), true, true, Some(api_key), Some(client)) =
+            (&url, host, https, resolved, &api_key, &client)
+        {
+            let user =
+                user::check_user(url, api_key, host, client, timeout, opts, &mut report).await;
+            if opts.send_real_heartbeat {
+                if !matches!(
+                    user,
+                    UserCheck::Rejected
+                        | UserCheck::RateLimited
+                        | UserCheck::Unreachable
+                        | UserCheck::Intercepted
+                ) {
+                    heartbeat::send_heartbeat(
+                        url,
+                        api_key,
+                        host,
+                        client,
+                        timeout,
+                        opts,
+                        &mut report,
+                    )
+                    .await;
+                }
+            } else if user == UserCheck::Unsupported {
+                report.push(Diagnostic::info(
+                    DiagnosticCode::HeartbeatProbeOnly,
+                    "Could not verify the API key without sending a heartbeat; pass --send-real-heartbeat to send one",
+                ));
+            }
+        } else {
+            report.push(Diagnostic::info(
+                DiagnosticCode::HeartbeatSkipped,
+                "Skipping heartbeat check because of earlier errors",
+            ));
+        }
+        report
+    };
+    let tls = async {
+        let mut report = Report::new();
+        if network
+            && want_tls
+            && https
+            && resolved
+            && client.is_some()
+            && let Some(url) = &url
+        {
+            tls::check_tls(url, &config.settings, opts, timeout, &mut report).await;
+        }
+        report
+    };
+    let update = async {
+        let mut report = Report::new();
+        if network
+            && want_update
+            && let Some(client) = &client
+        {
+            update::check_update(
+                update::CRATES_IO_URL,
+                client,
+                paths::update_cache_file().as_deref(),
+                timeout,
+                opts.verbose,
+                &mut report,
+            )
+            .await;
+        }
+        report
+    };
+    let endpoints = async {
+        let mut report = Report::new();
+        if network
+            && want_endpoints
+            && resolved
+            && https
+            && host == Some(WakaHost::Custom)
+            && let (Some(url), Some(api_key), Some(client)) = (&url, &api_key, &client)
+        {
+            bulk::check_endpoints(url, api_key, client, timeout, opts.verbose, &mut report).await;
+        }
+        report
+    };
+    // Each network probe has the deadline to itself, so those finishing in time are kept.
+    let (cli, tls, wakapi, endpoints, heartbeat, update) = tokio::join!(
+        cli,
+        until(deadline, tls),
+        until(deadline, wakapi),
+        until(deadline, endpoints),
+        until(deadline, heartbeat),
+        until(deadline, update),
+    );
+    let aborted = [&tls, &wakapi, &endpoints, &heartbeat, &update]
+        .iter()
+        .any(|v| v.is_none());
+    report.extend(update.unwrap_or_default());
+    report.extend(cli);
+    report.extend(client_report);
+    report.extend(tls.unwrap_or_default());
+    report.extend(wakapi.unwrap_or_default());
+    report.extend(endpoints.unwrap_or_default());
+    report.extend(heartbeat.unwrap_or_default());
+    if aborted && let Some((seconds, _)) = deadline {
+        report.push(deadline_reached(seconds));
+    }
+
+    if let Some(host) = host
+        && !report.iter().any(|d| d.level == Level::Error)
+    {
+        report.push(Diagnostic::ok(
+            DiagnosticCode::Configured,
+            format!("{host} is configured correctly!"),
+        ));
+    }
+
+    // Messages can quote command output or server responses, so make sure no key leaks through.
+    redact::scrub_report(
+        &mut report,
+        &[
+            api_key.as_deref(),
+            opts.api_key.as_deref(),
+            Some(config.settings.api_key.as_str()),
+        ],
+    );
+    report
+}
+
+/// Parse the API URL, falling back to the default when it is not set.
+fn check_url(config: &WakaConfig, opts: &CheckOptions, report: &mut Report) -> Option<Url> {
+    let api_url = match &opts.api_url {
+        Some(v) => {
+            report.push(Diagnostic::info(
+                DiagnosticCode::ApiUrlOverride,
+                "Using API URL from command line",
+            ));
+            v
+        }
+        None => &config.settings.api_url,
+    };
+    if api_url.is_empty() {
+        report.push(Diagnostic::warning(
+            DiagnosticCode::ApiUrlMissing,
+            "Wakatime API URL is not specified - assuming default \
+             (https://api.wakatime.com/api/v1)",
+        ));
+        Some(Url::parse("https://api.wakatime.com/api/v1").unwrap())
+    } else {
+        match Url::parse(api_url) {
+            Ok(v) => {
+                report.push(Diagnostic::ok(
+                    DiagnosticCode::ApiUrlValid,
+                    "Wakatime API URL is valid URL",
+                ));
+                Some(v)
+            }
+            Err(e) => {
+                report.push(Diagnostic::error(
+                    DiagnosticCode::ApiUrlInvalid,
+                    format!("Wakatime API URL is not valid URL (failed parsing with error {e})"),
+                ));
+                None
+            }
+        }
+    }
+}
+
+/// Read the known hosts file, if there is one.
+fn load_known_hosts(opts: &CheckOptions, report: &mut Report) -> Vec<String> {
+    let Some(path) = &opts.known_hosts_file else {
+        return Vec::new();
+    };
+    known_hosts::load(path).unwrap_or_else(|e| {
+        report.push(Diagnostic::warning(
+            DiagnosticCode::KnownHosts,
+            format!(
+                "Cannot read known hosts file {} with error \"{e}\"",
+                path.display()
+            ),
+        ));
+        Vec::new()
+    })
+}
+
+/// Add `host` to the known hosts file.
+fn remember_host(host: &str, opts: &CheckOptions, report: &mut Report) {
+    let Some(path) = &opts.known_hosts_file else {
+        report.push(Diagnostic::warning(
+            DiagnosticCode::KnownHosts,
+            "Cannot remember host because there is no known hosts file location",
+        ));
+        return;
+    };
+    match known_hosts::remember(path, host) {
+        Ok(()) => report.push(Diagnostic::info(
+            DiagnosticCode::KnownHosts,
+            format!(
+                "Remembered {host} as a known custom server in {}",
+                path.display()
+            ),
+        )),
+        Err(e) => report.push(Diagnostic::warning(
+            DiagnosticCode::KnownHosts,
+            format!(
+                "Cannot write known hosts file {} with error \"{e}\"",
+                path.display()
+            ),
+        )),
+    }
+}
+
+/// Work out the API key to use, running the vault command if needed.
+///
+/// Returns `None` if there is no usable key.
+async fn resolve_api_key(
+    config: &WakaConfig,
+    opts: &CheckOptions,
+    report: &mut Report,
+) -> Option<String> {
+    if let Some(api_key) = &opts.api_key {
+        report.push(Diagnostic::info(
+            DiagnosticCode::KeyOverride,
+            "Using API key from command line",
+        ));
+        return Some(api_key.clone());
+    }
+
+    let mut api_key = config.settings.api_key.clone();
+    if !config.settings.api_key_vault_cmd.is_empty() {
+        // wakatime uses the vault command whenever it is set, so its key is the one to check.
+        let vault_key =
+            match vault::run_vault_cmd(&config.settings.api_key_vault_cmd, vault::VAULT_TIMEOUT)
+                .await
+            {
+                Ok(v) => {
+                    report.push(Diagnostic::ok(
+                        DiagnosticCode::VaultOk,
+                        "Retrieved API key from vault command",
+                    ));
+                    v
+                }
+                Err(e) => {
+                    report.push(Diagnostic::error(DiagnosticCode::VaultFailed, e));
+                    return None;
+                }
+            };
+        let dual = vault::check_dual_key(&api_key, &vault_key);
+        if dual != vault::DualKeyCheck::NotBoth {
+            report.push(Diagnostic::warning(
+                DiagnosticCode::VaultDualKey,
+                "Both api_key and api_key_vault_cmd are set; wakatime will use the vault command",
+            ));
+        }
+        match dual {
+            vault::DualKeyCheck::NotBoth => {}
+            vault::DualKeyCheck::BothMatching => report.push(Diagnostic::ok(
+                DiagnosticCode::VaultDualKeyMatch,
+                "Vault command output matches api_key",
+            )),
+            vault::DualKeyCheck::BothDiffering => report.push(Diagnostic::warning(
+                DiagnosticCode::VaultDualKeyMismatch,
+                "Vault command output differs from api_key; api_key is ignored",
+            )),
+        }
+        api_key = vault_key;
+    } else if config::unquote(&api_key) != api_key {
+        report.push(
+            Diagnostic::warning(
+                DiagnosticCode::KeyQuoted,
+                "API key has surrounding quotes/whitespace that may break authentication",
+            )
+            .with_fix(Fix::UnquoteApiKey),
+        );
+        // Check the key itself, so the format diagnosis is about the key and not the quotes.
+        api_key = config::unquote(&api_key).to_string();
+    }
+
+    #[cfg(feature = "keyring")]
+    if api_key.is_empty() && config.settings.api_key_vault_cmd.is_empty() {
+        match crate::keychain::read_key() {
+            Ok(Some(v)) => {
+                report.push(Diagnostic::ok(
+                    DiagnosticCode::KeyKeyring,
+                    format!(
+                        "Retrieved API key from system keyring (service \"{}\")",
+                        crate::keychain::KEYRING_SERVICE
+                    ),
+                ));
+                api_key = v;
+            }
+            Ok(None) => {}
+            Err(e) => report.push(Diagnostic::warning(DiagnosticCode::KeyringFailed, e)),
+        }
+    }
+
+    if api_key.is_empty() {
+        report.push(Diagnostic::error(
+            DiagnosticCode::KeyMissing,
+            "No API key in file",
+        ));
+        return None;
+    }
+    Some(api_key)
+}
+
+/// Whether an API key has the format a host expects, from [`validate_api_key`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KeyValidation {
+    /// The key has the format of the host.
+    Valid,
+    /// The key is empty.
+    EmptyKey,
+    /// The key has the format of the other service: a `waka_` key for Hackatime, or a bare UUID
+    /// for Wakatime.
+    WrongPrefix,
+    /// The key is not a UUID, with or without the `waka_` prefix.
+    BadUuid,
+    /// The host has no known key format.
+    Unchecked,
+}
+
+/// Check `key` has the format `host` expects: a UUID for Hackatime, and `waka_` followed by a
+/// UUID for Wakatime.
+pub fn validate_api_key(key: &str, host: WakaHost) -> KeyValidation {
+    if key.is_empty() {
+        return KeyValidation::EmptyKey;
+    }
+    let is_uuid = |v: &str| uuid::Uuid::parse_str(v).is_ok();
+    let (own, other) = match host {
+        WakaHost::Hackatime => (is_uuid(key), key.strip_prefix("waka_").is_some_and(is_uuid)),
+        WakaHost::Wakatime => (key.strip_prefix("waka_").is_some_and(is_uuid), is_uuid(key)),
+        WakaHost::OldHackatime | WakaHost::Custom => return KeyValidation::Unchecked,
+    };
+    if own {
+        KeyValidation::Valid
+    } else if other {
+        KeyValidation::WrongPrefix
+    } else {
+        KeyValidation::BadUuid
+    }
+}
+
+/// Check `api_key` is in the format expected by `host`.
+fn check_key_format(api_key: &str, host: Option<WakaHost>, report: &mut Report) {
+    let Some(host) = host else {
+        return;
+    };
+    match validate_api_key(api_key, host) {
+        KeyValidation::Valid => report.push(Diagnostic::ok(
+            DiagnosticCode::KeyValid,
+            format!("{host} API key is in valid format"),
+        )),
+        KeyValidation::EmptyKey => report.push(Diagnostic::error(
+            DiagnosticCode::KeyMissing,
+            "No API key in file",
+        )),
+        KeyValidation::WrongPrefix if host == WakaHost::Hackatime => {
+            report.push(Diagnostic::warning(
+                DiagnosticCode::KeyCrossed,
+                "This looks like a Wakatime key (waka_ prefix) but your host is Hackatime",
+            ))
+        }
+        KeyValidation::WrongPrefix => report.push(Diagnostic::warning(
+            DiagnosticCode::KeyCrossed,
+            "This looks like a Hackatime key (no waka_ prefix) but your host is Wakatime",
+        )),
+        KeyValidation::BadUuid => report.push(Diagnostic::error(
+            DiagnosticCode::KeyInvalid,
+            format!("{host} API key is NOT in valid format"),
+        )),
+        KeyValidation::Unchecked => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn deadlines() {
+        let deadline = |seconds| {
+            Some((
+                seconds,
+                tokio::time::Instant::now() + Duration::from_millis(50),
+            ))
+        };
+        let mut report = Report::new();
+        assert_eq!(within(None, async { 1 }, &mut report).await, Some(1));
+        assert_eq!(within(deadline(1), async { 2 }, &mut report).await, Some(2));
+        assert!(report.is_empty());
+
+        let slow = tokio::time::sleep(Duration::from_secs(5));
+        assert_eq!(within(deadline(3), slow, &mut report).await, None);
+        assert_eq!(report[0].code, DiagnosticCode::Deadline);
+        assert_eq!(
+            report[0].message,
+            "Network checks aborted after reaching the 3s deadline"
+        );
+
+        // A probe finishing in time keeps its result when another is aborted.
+        let slow = tokio::time::sleep(Duration::from_secs(5));
+        assert_eq!(
+            tokio::join!(until(deadline(1), async { 3 }), until(deadline(1), slow)),
+            (Some(3), None)
+        );
+    }
+
+    /// Run [`resolve_api_key`] on `config_text` and return the key and the codes it produced.
+    async fn resolved_key(config_text: &str) -> (Option<String>, Vec<&'static str>) {
+        let config = config::parse_config(config_text).unwrap();
+        let mut report = Report::new();
+        let key = resolve_api_key(&config, &CheckOptions::default(), &mut report).await;
+        (key, report.iter().map(|d| d.code.as_str()).collect())
+    }
+
+    #[tokio::test]
+    async fn only_plaintext_key() {
+        assert_eq!(
+            resolved_key("[settings]\napi_key = 3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e\n").await,
+            (
+                Some("3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e".to_string()),
+                Vec::new()
+            )
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn vault_key_wins() {
+        let vault = "api_key_vault_cmd = echo 3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e\n";
+        for (api_key, codes) in [
+            ("", &["vault_ok"][..]),
+            (
+                "3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e",
+                &["vault_ok", "vault_dual_key", "vault_dual_key_match"],
+            ),
+            (
+                "not-a-key",
+                &["vault_ok", "vault_dual_key", "vault_dual_key_mismatch"],
+            ),
+        ] {
+            let (key, report) =
+                resolved_key(&format!("[settings]\napi_key = {api_key}\n{vault}")).await;
+            assert_eq!(key.as_deref(), Some("3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e"));
+            assert_eq!(report, codes, "{api_key}");
+        }
+    }
+
+    /// Run [`check_key_format`] and return the codes it produced.
+    fn key_codes(api_key: &str, host: WakaHost) -> Vec<&'static str> {
+        let mut report = Report::new();
+        check_key_format(api_key, Some(host), &mut report);
+        report.iter().map(|d| d.code.as_str()).collect()
+    }
+
+    #[test]
+    fn validates_api_keys() {
+        let uuid = "3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e";
+        let waka = "waka_3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e";
+        let cases = [
+            (uuid, WakaHost::Hackatime, KeyValidation::Valid),
+            (waka, WakaHost::Hackatime, KeyValidation::WrongPrefix),
+            ("not-a-key", WakaHost::Hackatime, KeyValidation::BadUuid),
+            (
+                "waka_not-a-key",
+                WakaHost::Hackatime,
+                KeyValidation::BadUuid,
+            ),
+            ("", WakaHost::Hackatime, KeyValidation::EmptyKey),
+            (waka, WakaHost::Wakatime, KeyValidation::Valid),
+            (uuid, WakaHost::Wakatime, KeyValidation::WrongPrefix),
+            ("waka_nope", WakaHost::Wakatime, KeyValidation::BadUuid),
+            ("waka_", WakaHost::Wakatime, KeyValidation::BadUuid),
+            ("", WakaHost::Wakatime, KeyValidation::EmptyKey),
+            (uuid, WakaHost::OldHackatime, KeyValidation::Unchecked),
+            ("anything", WakaHost::Custom, KeyValidation::Unchecked),
+            ("", WakaHost::Custom, KeyValidation::EmptyKey),
+        ];
+        for (key, host, expected) in cases {
+            assert_eq!(
+                validate_api_key(key, host),
+                expected,
+                "{key:?} for {host:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn key_formats() {
+        let uuid = "3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e";
+        let waka = "waka_3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e";
+        assert_eq!(key_codes(uuid, WakaHost::Hackatime), ["key_valid"]);
+        assert_eq!(key_codes(waka, WakaHost::Hackatime), ["key_crossed"]);
+        assert_eq!(key_codes(waka, WakaHost::Wakatime), ["key_valid"]);
+        assert_eq!(key_codes(uuid, WakaHost::Wakatime), ["key_crossed"]);
+        assert_eq!(key_codes("waka_nope", WakaHost::Wakatime), ["key_invalid"]);
+        assert!(key_codes("anything", WakaHost::Custom).is_empty());
+    }
+}
@@ -0,0 +1,377 @@
+//! Building the HTTP client used for network checks.
+
+use std::{fmt::Display, net::IpAddr, str::FromStr};
+
+use url::Url;
+
+use crate::{
+    checks::CheckOptions,
+    config::{self, WakaSettings},
+    diagnostic::{Diagnostic, DiagnosticCode, Report},
+    paths,
+    wakatime_cli::MIN_CLI_VERSION,
+};
+
+/// Proxy schemes supported by wakatime.
+const PROXY_SCHEMES: &[&str] = &["http", "https", "socks5", "socks5h"];
+
+/// User-Agent in the format wakatime-cli uses, e.g.
+/// `wakatime/v1.73.0 (linux-amd64) wakadoctor/0.2.0`.
+pub fn default_user_agent() -> String {
+    // wakatime-cli reports Go's names for the architecture.
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "x86" => "386",
+        other => other,
+    };
+    format!(
+        "wakatime/v{MIN_CLI_VERSION} ({}-{arch}) wakadoctor/{}",
+        std::env::consts::OS,
+        env!("CARGO_PKG_VERSION")
+    )
+}
+
+/// IP address family used for connections.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum IpVersion {
+    /// Whichever family the system picks.
+    #[default]
+    Auto,
+    /// IPv4 only.
+    V4,
+    /// IPv6 only.
+    V6,
+}
+
+impl IpVersion {
+    /// Unspecified local address to bind to, which restricts connections to this family.
+    fn local_address(self) -> Option<IpAddr> {
+        match self {
+            Self::Auto => None,
+            Self::V4 => Some(IpAddr::from([0, 0, 0, 0])),
+            Self::V6 => Some(IpAddr::from([0u16; 8])),
+        }
+    }
+
+    /// Whether `addr` can be connected to with this family.
+    pub fn allows(self, addr: &IpAddr) -> bool {
+        match self {
+            Self::Auto => true,
+            Self::V4 => addr.is_ipv4(),
+            Self::V6 => addr.is_ipv6(),
+        }
+    }
+}
+
+impl FromStr for IpVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "4" | "v4" | "ipv4" => Ok(Self::V4),
+            "6" | "v6" | "ipv6" => Ok(Self::V6),
+            _ => Err(format!("invalid IP version \"{s}\", expected 4, 6 or auto")),
+        }
+    }
+}
+
+impl Display for IpVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Auto => "any IP version",
+            Self::V4 => "IPv4",
+            Self::V6 => "IPv6",
+        })
+    }
+}
+
+/// TLS protocol version.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum TlsVersion {
+    /// TLS 1.1 or older, only ever negotiated and never required.
+    Legacy,
+    /// TLS 1.2.
+    V1_2,
+    /// TLS 1.3.
+    V1_3,
+}
+
+impl TlsVersion {
+    /// The matching reqwest version, or the newest legacy one.
+    pub(crate) fn to_reqwest(self) -> reqwest::tls::Version {
+        match self {
+            Self::Legacy => reqwest::tls::Version::TLS_1_1,
+            Self::V1_2 => reqwest::tls::Version::TLS_1_2,
+            Self::V1_3 => reqwest::tls::Version::TLS_1_3,
+        }
+    }
+}
+
+impl FromStr for TlsVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().trim_start_matches("tls") {
+            "1.2" | "12" => Ok(Self::V1_2),
+            "1.3" | "13" => Ok(Self::V1_3),
+            _ => Err(format!("invalid TLS version \"{s}\", expected 1.2 or 1.3")),
+        }
+    }
+}
+
+impl Display for TlsVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Legacy => "TLS 1.1 or older",
+            Self::V1_2 => "TLS 1.2",
+            Self::V1_3 => "TLS 1.3",
+        })
+    }
+}
+
+/// Restrict `builder` to TLS versions from `min` up to `max`.
+///
+/// The system TLS library cannot be limited to TLS 1.3, so rustls is used whenever TLS 1.3 is
+/// required.
+pub(crate) fn limit_tls(
+    mut builder: reqwest::ClientBuilder,
+    min: Option<TlsVersion>,
+    max: Option<TlsVersion>,
+) -> reqwest::ClientBuilder {
+    if let Some(v) = min {
+        if v == TlsVersion::V1_3 {
+            builder = builder.use_rustls_tls();
+        }
+        builder = builder.min_tls_version(v.to_reqwest());
+    }
+    if let Some(v) = max {
+        builder = builder.max_tls_version(v.to_reqwest());
+    }
+    builder
+}
+
+/// Build the HTTP client according to `settings` and `opts`, pushing diagnostics to `report`.
+///
+/// Returns `None` if the settings make it impossible to build a client.
+pub fn build_client(
+    settings: &WakaSettings,
+    opts: &CheckOptions,
+    report: &mut Report,
+) -> Option<reqwest::Client> {
+    let builder = limit_tls(client_builder(settings, opts, report)?, opts.min_tls, None);
+    match builder.build() {
+        Ok(v) => Some(v),
+        Err(e) => {
+            report.push(Diagnostic::error(
+                DiagnosticCode::ClientBuild,
+                format!("Cannot build HTTP client with error \"{e}\""),
+            ));
+            None
+        }
+    }
+}
+
+/// Start building an HTTP client according to `settings` and `opts`, pushing diagnostics to
+/// `report`.
+///
+/// Returns `None` if the settings make it impossible to build a client.
+pub(crate) fn client_builder(
+    settings: &WakaSettings,
+    opts: &CheckOptions,
+    report: &mut Report,
+) -> Option<reqwest::ClientBuilder> {
+    let mut builder = reqwest::Client::builder()
+        .user_agent(opts.user_agent.clone().unwrap_or_else(default_user_agent));
+
+    if let Some(v) = opts.ip_version.local_address() {
+        builder = builder.local_address(v);
+    }
+    if let Some(v) = opts.connect_timeout {
+        builder = builder.connect_timeout(std::time::Duration::from_secs(v));
+    }
+
+    let proxy = opts.proxy.as_deref().unwrap_or(&settings.proxy);
+    if !proxy.is_empty() {
+        builder = builder.proxy(check_proxy(proxy, report)?);
+    }
+
+    if config::is_enabled(&settings.no_ssl_verify) {
+        report.push(Diagnostic::warning(
+            DiagnosticCode::NoSslVerify,
+            "no_ssl_verify is enabled; TLS certificates are NOT verified, which is insecure",
+        ));
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    if !settings.ssl_certs_file.is_empty() {
+        for cert in load_certs(&settings.ssl_certs_file, report)? {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    Some(builder)
+}
+
+/// Send `request`, logging it and its response to stderr if `verbose`, with credentials redacted.
+pub(crate) async fn send(
+    request: reqwest::RequestBuilder,
+    verbose: bool,
+) -> reqwest::Result<reqwest::Response> {
+    if !verbose {
+        return request.send().await;
+    }
+    let (client, request) = request.build_split();
+    let request = request?;
+    // Print each block at once, as checks run concurrently.
+    eprint!(
+        "> {} {}\n{}\n",
+        request.method(),
+        request.url(),
+        log_headers('>', request.headers())
+    );
+    let response = client.execute(request).await;
+    match &response {
+        Ok(v) => eprint!(
+            "< {:?} {}\n{}\n",
+            v.version(),
+            v.status(),
+            log_headers('<', v.headers())
+        ),
+        Err(e) => eprint!("< {e}\n\n"),
+    }
+    response
+}
+
+/// Format `headers` one per line after `prefix`, hiding credentials.
+fn log_headers(prefix: char, headers: &reqwest::header::HeaderMap) -> String {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if name == reqwest::header::AUTHORIZATION
+                || name == reqwest::header::PROXY_AUTHORIZATION
+            {
+                "<redacted>".into()
+            } else {
+                String::from_utf8_lossy(value.as_bytes())
+            };
+            format!("{prefix} {name}: {value}\n")
+        })
+        .collect()
+}
+
+/// Validate the proxy URL `proxy`.
+fn check_proxy(proxy: &str, report: &mut Report) -> Option<reqwest::Proxy> {
+    let url = match Url::parse(proxy) {
+        Ok(v) if PROXY_SCHEMES.contains(&v.scheme()) => v,
+        _ => {
+            report.push(Diagnostic::error(
+                DiagnosticCode::ProxyInvalid,
+                format!(
+                    "Invalid proxy URL (must start with one of {})",
+                    PROXY_SCHEMES
+                        .iter()
+                        .map(|v| format!("{v}://"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            ));
+            return None;
+        }
+    };
+
+    match reqwest::Proxy::all(url.as_str()) {
+        Ok(v) => {
+            let mut shown = url.clone();
+            if shown.password().is_some() {
+                let _ = shown.set_password(Some("****"));
+            }
+            report.push(Diagnostic::ok(
+                DiagnosticCode::Proxy,
+                format!("Using proxy {shown}"),
+            ));
+            Some(v)
+        }
+        Err(e) => {
+            report.push(Diagnostic::error(
+                DiagnosticCode::ProxyInvalid,
+                format!("Invalid proxy URL (failed parsing with error {e})"),
+            ));
+            None
+        }
+    }
+}
+
+/// Load the CA bundle at `path`.
+fn load_certs(path: &str, report: &mut Report) -> Option<Vec<reqwest::Certificate>> {
+    let path = match paths::expand_home(path) {
+        Ok(v) => v,
+        Err(e) => {
+            report.push(Diagnostic::error(
+                DiagnosticCode::SslCertsFile,
+                format!("Cannot expand ssl_certs_file location with error \"{e}\""),
+            ));
+            return None;
+        }
+    };
+
+    let pem = match std::fs::read(&path) {
+        Ok(v) => v,
+        Err(e) => {
+            report.push(Diagnostic::error(
+                DiagnosticCode::SslCertsFile,
+                format!("Cannot read ssl_certs_file \"{path}\" with error \"{e}\""),
+            ));
+            return None;
+        }
+    };
+
+    match reqwest::Certificate::from_pem_bundle(&pem) {
+        Ok(v) if !v.is_empty() => {
+            report.push(Diagnostic::ok(
+                DiagnosticCode::SslCertsFile,
+                format!("Loaded {} certificate(s) from ssl_certs_file", v.len()),
+            ));
+            Some(v)
+        }
+        Ok(_) => {
+            report.push(Diagnostic::error(
+                DiagnosticCode::SslCertsFile,
+                format!("ssl_certs_file \"{path}\" contains no PEM certificates"),
+            ));
+            None
+        }
+        Err(e) => {
+            report.push(Diagnostic::error(
+                DiagnosticCode::SslCertsFile,
+                format!("Cannot parse ssl_certs_file \"{path}\" as PEM with error \"{e}\""),
+            ));
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ip_versions() {
+        assert_eq!("4".parse(), Ok(IpVersion::V4));
+        assert_eq!("IPv6".parse(), Ok(IpVersion::V6));
+        assert_eq!("auto".parse(), Ok(IpVersion::Auto));
+        assert!("5".parse::<IpVersion>().is_err());
+        assert!(IpVersion::V6.allows(&"::1".parse().unwrap()));
+        assert!(!IpVersion::V6.allows(&"127.0.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn tls_versions() {
+        assert_eq!("1.2".parse(), Ok(TlsVersion::V1_2));
+        assert_eq!("TLS1.3".parse(), Ok(TlsVersion::V1_3));
+        assert!("1.1".parse::<TlsVersion>().is_err());
+        assert!(TlsVersion::Legacy < TlsVersion::V1_2);
+        assert_eq!(TlsVersion::V1_3.to_string(), "TLS 1.3");
+    }
+}
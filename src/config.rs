@@ -0,0 +1,931 @@
+//! Reading and parsing the wakatime config file.
+
+use std::{
+    collections::BTreeMap,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticCode, Report},
+    fix::Fix,
+    paths,
+};
+
+/// The `[settings]` section of the wakatime config.
+///
+/// Boolean settings are kept as written, so that a value that is not a boolean can be reported
+/// without failing the parse, and read with [`parse_bool`] or [`is_enabled`].
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct WakaSettings {
+    /// Whether wakatime writes debug logs.
+    pub debug: String,
+    /// Path of the wakatime log file, if not the default.
+    pub log_file: String,
+    /// The API key.
+    pub api_key: String,
+    /// Command printing the API key, used instead of `api_key`.
+    pub api_key_vault_cmd: String,
+    /// Base URL of the API.
+    pub api_url: String,
+    /// Hide file names.
+    pub hide_file_names: String,
+    /// Hide project names.
+    pub hide_project_names: String,
+    /// Hide branch names.
+    pub hide_branch_names: String,
+    /// Hide dependencies.
+    pub hide_dependencies: String,
+    /// Hide the project folder.
+    pub hide_project_folder: String,
+    /// Request timeout in seconds.
+    pub timeout: String,
+    /// Minimum number of seconds between heartbeats.
+    pub heartbeat_rate_limit_seconds: String,
+    /// Proxy URL used for requests.
+    pub proxy: String,
+    /// Disable TLS certificate verification.
+    pub no_ssl_verify: String,
+    /// Path to a CA bundle used to verify TLS certificates.
+    pub ssl_certs_file: String,
+    /// Hostname reported with heartbeats instead of the machine's, if set.
+    pub hostname: Option<String>,
+    /// Regexes of paths to exclude, one per line.
+    pub exclude: String,
+    /// Regexes of paths to include even if excluded, one per line.
+    pub include: String,
+    /// Whether heartbeats outside a detected project are dropped.
+    pub exclude_unknown_project: String,
+    /// Whether only files in a folder with a `.wakatime-project` file are tracked.
+    pub include_only_with_project_file: String,
+    /// Whether editor plugins show wakatime in their status bar.
+    pub status_bar_enabled: String,
+    /// Whether the status bar shows today's coding time.
+    pub status_bar_coding_activity: String,
+}
+
+/// `value` without surrounding whitespace and one pair of matching surrounding quotes.
+pub fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    ['"', '\'']
+        .iter()
+        .find_map(|q| value.strip_prefix(*q)?.strip_suffix(*q))
+        .map_or(value, str::trim)
+}
+
+/// Parse an INI boolean the way wakatime-cli does, with an empty value meaning `false`.
+pub fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" => Some(true),
+        "false" | "0" | "" => Some(false),
+        _ => None,
+    }
+}
+
+/// Whether the boolean setting `value` is on. A value that is not a boolean is off.
+pub fn is_enabled(value: &str) -> bool {
+    parse_bool(value) == Some(true)
+}
+
+/// Separator used to fold continuation lines into a single INI value, as serde_ini has no
+/// multi-line values.
+const CONTINUATION_SEPARATOR: char = '\u{1f}';
+
+/// Fold indented continuation lines into the value of the key above them, joined with
+/// [`CONTINUATION_SEPARATOR`].
+fn join_continuation_lines(text: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut in_value = false;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with(['#', ';']) {
+            // Comments between continuation lines would split the value, so drop them.
+            if !in_value {
+                lines.push(trimmed.to_string());
+            }
+            continue;
+        }
+        if in_value
+            && line.starts_with([' ', '\t'])
+            && !trimmed.is_empty()
+            && let Some(last) = lines.last_mut()
+        {
+            last.push(CONTINUATION_SEPARATOR);
+            last.push_str(trimmed);
+            continue;
+        }
+        in_value = !trimmed.starts_with('[') && trimmed.contains('=');
+        lines.push(line.to_string());
+    }
+    lines.join("\n")
+}
+
+/// Split a possibly multi-line setting value into its non-empty lines.
+pub fn value_lines(value: &str) -> Vec<&str> {
+    value
+        .split([CONTINUATION_SEPARATOR, '\n'])
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+/// Key names of `key = value` lines in `text` that come before any section header.
+fn top_level_keys(text: &str) -> Vec<&str> {
+    text.lines()
+        .map(str::trim)
+        .take_while(|line| !line.starts_with('['))
+        .filter(|line| !line.starts_with(['#', ';']))
+        .filter_map(|line| Some(line.split_once('=')?.0.trim()))
+        .collect()
+}
+
+/// Key names of indented `key = value` lines in `text`, which are read as continuations of the
+/// value above them rather than as keys.
+///
+/// Continuation lines of `exclude` and `include` are regexes, which rarely start with a plain
+/// identifier followed by `=`, so those are not mistaken for keys.
+fn indented_keys(text: &str) -> Vec<&str> {
+    text.lines()
+        .filter(|line| line.starts_with([' ', '\t']))
+        .filter_map(|line| Some(line.split_once('=')?.0.trim()))
+        .filter(|key| {
+            key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && key
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        })
+        .collect()
+}
+
+/// Keys that appear more than once in the `[section]` section of `text`, in order of their
+/// second appearance.
+fn duplicate_keys<'a>(text: &'a str, section: &str) -> Vec<&'a str> {
+    let mut current = None;
+    let mut seen = Vec::new();
+    let mut duplicates = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            current = Some(name.trim());
+        } else if current == Some(section)
+            && !line.starts_with(['#', ';'])
+            && let Some((key, _)) = line.split_once('=')
+        {
+            let key = key.trim();
+            if seen.contains(&key) {
+                if !duplicates.contains(&key) {
+                    duplicates.push(key);
+                }
+            } else {
+                seen.push(key);
+            }
+        }
+    }
+    duplicates
+}
+
+/// Whether `text` has a `[name]` section header.
+fn has_section(text: &str, name: &str) -> bool {
+    text.lines().any(|line| {
+        line.trim()
+            .strip_prefix('[')
+            .and_then(|v| v.strip_suffix(']'))
+            .is_some_and(|v| v.trim() == name)
+    })
+}
+
+/// Lines of every `[name]` section of `text`, without the section headers.
+fn section_lines<'a>(text: &'a str, name: &str) -> Vec<&'a str> {
+    let mut current = None;
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        if let Some(v) = line
+            .trim()
+            .strip_prefix('[')
+            .and_then(|v| v.strip_suffix(']'))
+        {
+            current = Some(v.trim());
+        } else if current == Some(name) {
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+/// Insert `lines` at the end of the last `[settings]` section of `text`, so they override the
+/// values above them.
+fn with_settings_lines(text: &str, lines: &[&str]) -> String {
+    let mut in_settings = false;
+    let mut end = None;
+    for (i, line) in text.lines().enumerate() {
+        if let Some(v) = line
+            .trim()
+            .strip_prefix('[')
+            .and_then(|v| v.strip_suffix(']'))
+        {
+            if in_settings {
+                end = Some(i);
+            }
+            in_settings = v.trim() == "settings";
+        }
+    }
+    let mut out = text.lines().collect::<Vec<_>>();
+    let end = if in_settings {
+        out.len()
+    } else {
+        end.unwrap_or(out.len())
+    };
+    out.splice(end..end, lines.iter().copied());
+    out.join("\n")
+}
+
+/// Apply the profile `name` to the config `text`, returning the merged text and where the profile
+/// came from.
+///
+/// The profile is the `[settings.<name>]` section of `text` if there is one, or else the
+/// `[settings]` section of [`paths::profile_file`]. Its values override those of `[settings]` key
+/// by key.
+fn apply_profile(text: &str, name: &str) -> Result<(String, String), String> {
+    let section = format!("settings.{name}");
+    if has_section(text, &section) {
+        let lines = section_lines(text, &section);
+        return Ok((with_settings_lines(text, &lines), format!("[{section}]")));
+    }
+    let Some(path) = paths::profile_file(name) else {
+        return Err(format!(
+            "Profile \"{name}\" not found: no [{section}] section and no config directory"
+        ));
+    };
+    match std::fs::read_to_string(&path) {
+        Ok(v) => {
+            let profile = join_continuation_lines(&v);
+            let lines = section_lines(&profile, "settings");
+            Ok((
+                with_settings_lines(text, &lines),
+                path.display().to_string(),
+            ))
+        }
+        Err(e) => Err(format!(
+            "Profile \"{name}\" not found: no [{section}] section, and cannot read {} with error \"{e}\"",
+            path.display()
+        )),
+    }
+}
+
+/// Drop every `key = value` line that is overridden by a later line with the same key in the same
+/// section, as wakatime uses the last value but serde rejects duplicate fields.
+fn drop_shadowed_keys(text: &str) -> String {
+    /// Section and key of a `key = value` line.
+    fn entry<'a>(section: &'a str, line: &'a str) -> Option<(&'a str, &'a str)> {
+        let line = line.trim();
+        if line.starts_with(['#', ';', '[']) {
+            return None;
+        }
+        Some((section, line.split_once('=')?.0.trim()))
+    }
+
+    let mut section = "";
+    let entries = text
+        .lines()
+        .map(|line| {
+            if let Some(name) = line
+                .trim()
+                .strip_prefix('[')
+                .and_then(|v| v.strip_suffix(']'))
+            {
+                section = name.trim();
+            }
+            (line, entry(section, line))
+        })
+        .collect::<Vec<_>>();
+
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(i, (_, entry))| {
+            entry.is_none() || !entries[i + 1..].iter().any(|(_, later)| later == entry)
+        })
+        .map(|(_, (line, _))| *line)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Expand `${VAR}` and `$VAR` in `value` with `lookup`, returning the expanded value and the names
+/// of variables `lookup` does not know, whose references are left as they are.
+///
+/// `$$` is a literal `$`, as is a `$` not followed by a variable name.
+fn expand_vars(value: &str, lookup: impl Fn(&str) -> Option<String>) -> (String, Vec<String>) {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut out = String::new();
+    let mut undefined = Vec::new();
+    let mut rest = value;
+    while let Some(i) = rest.find('$') {
+        out.push_str(&rest[..i]);
+        let after = &rest[i + 1..];
+        if let Some(v) = after.strip_prefix('$') {
+            out.push('$');
+            rest = v;
+            continue;
+        }
+        let (name, reference_len) = if let Some(braced) = after.strip_prefix('{')
+            && let Some(end) = braced.find('}')
+        {
+            (&braced[..end], end + 2)
+        } else {
+            let end = after.find(|c| !is_name_char(c)).unwrap_or(after.len());
+            (&after[..end], end)
+        };
+        let reference = &rest[i..i + 1 + reference_len];
+        if name.is_empty()
+            || !name.chars().all(is_name_char)
+            || name.starts_with(|c: char| c.is_ascii_digit())
+        {
+            out.push_str(reference);
+        } else if let Some(v) = lookup(name) {
+            out.push_str(&v);
+        } else {
+            out.push_str(reference);
+            undefined.push(name.to_string());
+        }
+        rest = &rest[i + 1 + reference_len..];
+    }
+    out.push_str(rest);
+    (out, undefined)
+}
+
+/// Expand environment variables in the string values of `settings`, pushing diagnostics to
+/// `report`.
+pub fn expand_env(settings: &mut WakaSettings, report: &mut Report) {
+    let fields = [
+        ("api_key", &mut settings.api_key),
+        ("api_key_vault_cmd", &mut settings.api_key_vault_cmd),
+        ("api_url", &mut settings.api_url),
+        ("timeout", &mut settings.timeout),
+        ("proxy", &mut settings.proxy),
+        ("ssl_certs_file", &mut settings.ssl_certs_file),
+        ("exclude", &mut settings.exclude),
+        ("include", &mut settings.include),
+    ];
+    for (key, value) in fields {
+        let (expanded, undefined) = expand_vars(value, |name| std::env::var(name).ok());
+        for name in undefined {
+            report.push(Diagnostic::warning(
+                DiagnosticCode::EnvUndefined,
+                format!("{key} references undefined environment variable {name}"),
+            ));
+        }
+        if expanded != *value {
+            report.push(Diagnostic::info(
+                DiagnosticCode::EnvExpand,
+                format!("Expanded environment variables in {key}"),
+            ));
+            *value = expanded;
+        }
+    }
+}
+
+/// The last value of `key` in the `[settings]` sections of `text`.
+fn settings_value<'a>(text: &'a str, key: &str) -> Option<&'a str> {
+    section_lines(text, "settings")
+        .into_iter()
+        .filter(|line| !line.trim_start().starts_with(['#', ';']))
+        .filter_map(|line| line.split_once('='))
+        .filter(|(k, _)| k.trim() == key)
+        .map(|(_, v)| v.trim())
+        .next_back()
+}
+
+/// Merge the file named by `import_cfg` in `text` into it, recursively, pushing diagnostics to
+/// `report`.
+///
+/// Relative paths are resolved against `dir`, the directory of the file `text` was read from.
+/// Imported values override those of the importing file, as in wakatime-cli. `stack` holds the
+/// files being imported, to detect cycles. Returns `None` if an import is missing or cyclic.
+fn resolve_imports(
+    text: String,
+    dir: &Path,
+    stack: &mut Vec<PathBuf>,
+    report: &mut Report,
+) -> Option<String> {
+    let text = join_continuation_lines(&text);
+    let Some(import) = settings_value(&text, "import_cfg").filter(|v| !v.is_empty()) else {
+        return Some(text);
+    };
+    let path = match paths::expand_home(import) {
+        Ok(v) => dir.join(v),
+        Err(e) => {
+            report.push(Diagnostic::error(
+                DiagnosticCode::ConfigImport,
+                format!("Cannot expand import_cfg location with error \"{e}\""),
+            ));
+            return None;
+        }
+    };
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+    if stack.contains(&canonical) {
+        report.push(Diagnostic::error(
+            DiagnosticCode::ConfigImport,
+            format!(
+                "import_cfg {} imports itself through {}",
+                path.display(),
+                stack
+                    .iter()
+                    .map(|v| v.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(" -> ")
+            ),
+        ));
+        return None;
+    }
+    let imported = match std::fs::read_to_string(&path) {
+        Ok(v) => v,
+        Err(e) => {
+            report.push(Diagnostic::error(
+                DiagnosticCode::ConfigImport,
+                format!(
+                    "Cannot read import_cfg {} with error \"{e}\"",
+                    path.display()
+                ),
+            ));
+            return None;
+        }
+    };
+    report.push(Diagnostic::info(
+        DiagnosticCode::ConfigImport,
+        format!("Imported additional settings from {}", path.display()),
+    ));
+
+    stack.push(canonical);
+    let imported = resolve_imports(
+        imported,
+        path.parent().unwrap_or(Path::new(".")),
+        stack,
+        report,
+    );
+    stack.pop();
+    Some(with_settings_lines(
+        &text,
+        &section_lines(&imported?, "settings"),
+    ))
+}
+
+/// Parse the text of a config file.
+pub fn parse_config(text: &str) -> Result<WakaConfig, serde_ini::de::Error> {
+    serde_ini::from_str(&drop_shadowed_keys(&join_continuation_lines(
+        &strip_carriage_returns(text),
+    )))
+}
+
+/// Turn CRLF line endings into LF and drop any other `\r`, which configs edited on Windows leave
+/// at the end of values.
+fn strip_carriage_returns(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "")
+}
+
+/// The wakatime config file.
+#[derive(Deserialize, Clone, Debug)]
+pub struct WakaConfig {
+    /// The `[settings]` section.
+    pub settings: WakaSettings,
+    /// The `[projectmap]` section, mapping path regexes to project names.
+    #[serde(default)]
+    pub projectmap: BTreeMap<String, String>,
+    /// Where the config was read from, or `None` for stdin.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+/// Decode the config `bytes` as UTF-8, replacing invalid sequences and warning in `report` if
+/// there are any.
+fn decode(bytes: Vec<u8>, report: &mut Report) -> String {
+    String::from_utf8(bytes).unwrap_or_else(|e| {
+        report.push(Diagnostic::warning(
+            DiagnosticCode::ConfigNotUtf8,
+            "Config file is not valid UTF-8; non-ASCII characters may be corrupted",
+        ));
+        String::from_utf8_lossy(e.as_bytes()).into_owned()
+    })
+}
+
+/// Expand a leading `~` in the config `location` to `home`.
+///
+/// Returns `None`, with an error in `report`, if there is a `~` but no home directory, rather
+/// than reading a file literally named `~`.
+fn expand_location(location: &str, home: Option<PathBuf>, report: &mut Report) -> Option<String> {
+    match paths::expand_home_with(location, home) {
+        Ok(v) => Some(v),
+        Err(_) => {
+            report.push(Diagnostic::error(
+                DiagnosticCode::ConfigPathExpand,
+                "Could not expand '~' in config path (no home directory found); pass an absolute --config-location",
+            ));
+            None
+        }
+    }
+}
+
+/// Read and parse the config at `location` (`-` for stdin) with `profile` applied, pushing
+/// diagnostics to `report`.
+///
+/// Returns `None` if the config could not be read or parsed.
+pub fn load_config(
+    location: &str,
+    profile: Option<&str>,
+    report: &mut Report,
+) -> Option<WakaConfig> {
+    let (config_text, path) = if location == "-" {
+        let mut v = Vec::new();
+        match std::io::stdin().read_to_end(&mut v) {
+            Ok(_) => {
+                report.push(Diagnostic::ok(
+                    DiagnosticCode::ConfigRead,
+                    "Read Wakatime config from stdin",
+                ));
+                (decode(v, report), None)
+            }
+            Err(e) => {
+                report.push(Diagnostic::error(
+                    DiagnosticCode::ConfigRead,
+                    format!("Cannot read Wakatime config from stdin with error \"{e}\""),
+                ));
+                return None;
+            }
+        }
+    } else {
+        let location = expand_location(location, dirs::home_dir(), report)?;
+
+        let location = if Path::new(&location).is_dir() {
+            match paths::find_config_in_dir(Path::new(&location)) {
+                Ok(v) => {
+                    report.push(Diagnostic::info(
+                        DiagnosticCode::ConfigDir,
+                        format!("Using {} found in directory {location}", v.display()),
+                    ));
+                    v.to_string_lossy().into_owned()
+                }
+                Err(searched) => {
+                    report.push(Diagnostic::error(
+                        DiagnosticCode::ConfigDir,
+                        format!(
+                            "{location} is a directory, but none of {} exist",
+                            searched
+                                .iter()
+                                .map(|v| v.display().to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    ));
+                    return None;
+                }
+            }
+        } else {
+            location
+        };
+
+        let absolute = std::path::absolute(&location).unwrap_or(PathBuf::from(&location));
+        report.push(Diagnostic::info(
+            DiagnosticCode::ConfigPath,
+            format!("Reading config from: {}", absolute.display()),
+        ));
+
+        match std::fs::read(&location) {
+            Ok(v) => {
+                report.push(Diagnostic::ok(
+                    DiagnosticCode::ConfigRead,
+                    "Successfully read Wakatime config",
+                ));
+                (decode(v, report), Some(PathBuf::from(location)))
+            }
+            Err(e) => {
+                report.push(Diagnostic::error(
+                    DiagnosticCode::ConfigRead,
+                    format!("Cannot read Wakatime config with error \"{e}\""),
+                ));
+                return None;
+            }
+        }
+    };
+
+    if cfg!(unix) && config_text.contains("\r\n") {
+        report.push(Diagnostic::info(
+            DiagnosticCode::ConfigCrlf,
+            "Config has Windows (CRLF) line endings; wakadoctor strips them, but some tools may misread multi-line values",
+        ));
+    }
+
+    if config_text.trim().is_empty() {
+        report.push(Diagnostic::error(
+            DiagnosticCode::ConfigEmpty,
+            "Config file is empty",
+        ));
+        return None;
+    }
+
+    let indented = indented_keys(&config_text);
+    if !indented.is_empty() {
+        report.push(Diagnostic::info(
+            DiagnosticCode::ConfigIndented,
+            format!(
+                "Keys appear indented; INI keys must start at column 0 (indented: {})",
+                indented.join(", ")
+            ),
+        ));
+    }
+
+    let joined = join_continuation_lines(&config_text);
+    for section in ["settings", "projectmap"] {
+        for key in duplicate_keys(&joined, section) {
+            report.push(Diagnostic::warning(
+                DiagnosticCode::ConfigDuplicateKey,
+                format!("Duplicate key '{key}' in [{section}]; only one value is used"),
+            ));
+        }
+    }
+
+    if !has_section(&config_text, "settings") {
+        let keys = top_level_keys(&config_text);
+        let found = if keys.is_empty() {
+            String::new()
+        } else {
+            format!(" (found top-level keys: {})", keys.join(", "))
+        };
+        report.push(
+            Diagnostic::error(
+                DiagnosticCode::ConfigNoSettings,
+                format!("Config has no [settings] section; keys must live under [settings]{found}"),
+            )
+            .with_fix(Fix::AddSettingsHeader),
+        );
+        return None;
+    }
+
+    let dir = path
+        .as_deref()
+        .and_then(Path::parent)
+        .unwrap_or(Path::new("."))
+        .to_path_buf();
+    let mut stack = path
+        .iter()
+        .map(|v| v.canonicalize().unwrap_or(v.clone()))
+        .collect();
+    let config_text = resolve_imports(config_text, &dir, &mut stack, report)?;
+
+    let config_text = match profile {
+        Some(name) => match apply_profile(&join_continuation_lines(&config_text), name) {
+            Ok((v, source)) => {
+                report.push(Diagnostic::info(
+                    DiagnosticCode::Profile,
+                    format!("Using profile \"{name}\" from {source}"),
+                ));
+                v
+            }
+            Err(e) => {
+                report.push(Diagnostic::error(DiagnosticCode::Profile, e));
+                return None;
+            }
+        },
+        None => config_text,
+    };
+
+    match parse_config(&config_text) {
+        Ok(v) => {
+            report.push(Diagnostic::ok(
+                DiagnosticCode::ConfigParse,
+                "Successfully parsed Wakatime config",
+            ));
+            Some(WakaConfig { path, ..v })
+        }
+        Err(e) => {
+            report.push(Diagnostic::error(
+                DiagnosticCode::ConfigParse,
+                format!("Cannot parse Wakatime config with error \"{e}\""),
+            ));
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_booleans() {
+        let config = parse_config("[settings]\ndebug = true\nno_ssl_verify = FALSE\n").unwrap();
+        assert!(is_enabled(&config.settings.debug));
+        assert!(!is_enabled(&config.settings.no_ssl_verify));
+        let config = parse_config("[settings]\ndebug = maybe\n").unwrap();
+        assert_eq!(config.settings.debug, "maybe");
+        assert!(!is_enabled(&config.settings.debug));
+    }
+
+    #[test]
+    fn unquotes_values() {
+        assert_eq!(unquote("abc"), "abc");
+        assert_eq!(unquote("\"abc\""), "abc");
+        assert_eq!(unquote(" ' abc ' "), "abc");
+        assert_eq!(unquote("\"abc'"), "\"abc'");
+        assert_eq!(unquote("\""), "\"");
+    }
+
+    #[test]
+    fn detects_missing_settings_section() {
+        let text = "# my config\napi_key = x\napi_url = https://example.com\n";
+        assert!(!has_section(text, "settings"));
+        assert_eq!(top_level_keys(text), ["api_key", "api_url"]);
+        assert!(has_section("[ settings ]\napi_key = x\n", "settings"));
+    }
+
+    #[test]
+    fn expands_location() {
+        let mut report = Report::new();
+        assert_eq!(
+            expand_location(
+                "~/.wakatime.cfg",
+                Some(PathBuf::from("/home/user")),
+                &mut report
+            ),
+            Some("/home/user/.wakatime.cfg".to_string())
+        );
+        assert_eq!(
+            expand_location("/etc/wakatime.cfg", None, &mut report),
+            Some("/etc/wakatime.cfg".to_string())
+        );
+        assert!(report.is_empty());
+
+        assert_eq!(expand_location("~/.wakatime.cfg", None, &mut report), None);
+        assert_eq!(report[0].code, DiagnosticCode::ConfigPathExpand);
+        assert_eq!(
+            report[0].message,
+            "Could not expand '~' in config path (no home directory found); pass an absolute --config-location"
+        );
+    }
+
+    #[test]
+    fn finds_indented_keys() {
+        let text = "[settings]\n  api_url = x\n\tapi_key=y\nexclude =\n    ^a=b$\n    .*\\.env\n  # a = b\n";
+        assert_eq!(indented_keys(text), ["api_url", "api_key"]);
+    }
+
+    #[test]
+    fn finds_duplicate_keys() {
+        let text = "[settings]\napi_key = a\nexclude =\n  api_key = b\napi_key = c\napi_key = d\n[other]\nx = 1\n[settings]\nx = 2\nx = 3\n";
+        assert_eq!(
+            duplicate_keys(&join_continuation_lines(text), "settings"),
+            ["api_key", "x"]
+        );
+        let config = parse_config("[settings]\napi_key = a\napi_key = b\n").unwrap();
+        assert_eq!(config.settings.api_key, "b");
+    }
+
+    #[test]
+    fn parses_multi_line_values() {
+        let config = parse_config(
+            "[settings]\nexclude =\n    ^COMMIT_EDITMSG$\n    # comment\n    ^TAG_EDITMSG$\ninclude = .*\napi_key = x\n",
+        )
+        .unwrap();
+        assert_eq!(
+            value_lines(&config.settings.exclude),
+            ["^COMMIT_EDITMSG$", "^TAG_EDITMSG$"]
+        );
+        assert_eq!(value_lines(&config.settings.include), [".*"]);
+        assert_eq!(config.settings.api_key, "x");
+    }
+
+    #[test]
+    fn parses_crlf_line_endings() {
+        let config = parse_config(
+            "[settings]\r\nexclude =\r\n    ^COMMIT_EDITMSG$\r\n    ^TAG_EDITMSG$\r\napi_key = x\r\nhostname = box\r\r\n",
+        )
+        .unwrap();
+        assert_eq!(
+            value_lines(&config.settings.exclude),
+            ["^COMMIT_EDITMSG$", "^TAG_EDITMSG$"]
+        );
+        assert_eq!(config.settings.api_key, "x");
+        assert_eq!(config.settings.hostname.as_deref(), Some("box"));
+        assert_eq!(value_lines("^a$\r\n^b$\r"), ["^a$", "^b$"]);
+    }
+
+    #[test]
+    fn parses_projectmap() {
+        let config = parse_config(
+            "[settings]\napi_key = x\n[projectmap]\n^/work/(.*)/ = work-{0}\n^/home/ = home\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.projectmap.into_iter().collect::<Vec<_>>(),
+            [
+                ("^/home/".to_string(), "home".to_string()),
+                ("^/work/(.*)/".to_string(), "work-{0}".to_string())
+            ]
+        );
+        assert!(parse_config("[settings]\n").unwrap().projectmap.is_empty());
+    }
+
+    #[test]
+    fn parses_optional_hostname() {
+        let config = parse_config("[settings]\napi_key = x\n").unwrap();
+        assert_eq!(config.settings.hostname, None);
+        let config = parse_config("[settings]\nhostname =\n").unwrap();
+        assert_eq!(config.settings.hostname.as_deref(), Some(""));
+    }
+
+    #[test]
+    fn expands_variables() {
+        let lookup = |name: &str| (name == "KEY").then(|| "abc".to_string());
+        assert_eq!(expand_vars("${KEY}", lookup), ("abc".to_string(), vec![]));
+        assert_eq!(
+            expand_vars("x-$KEY/y", lookup),
+            ("x-abc/y".to_string(), vec![])
+        );
+        assert_eq!(
+            expand_vars("${MISSING} $OTHER", lookup),
+            (
+                "${MISSING} $OTHER".to_string(),
+                vec!["MISSING".to_string(), "OTHER".to_string()]
+            )
+        );
+        assert_eq!(
+            expand_vars("$$KEY costs $5 ${", lookup),
+            ("$KEY costs $5 ${".to_string(), vec![])
+        );
+    }
+
+    #[test]
+    fn imports_configs() {
+        let dir = std::env::temp_dir().join(format!("wakadoctor-import-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(
+            dir.join("sub/imported.cfg"),
+            "[settings]\napi_key = imported\nimport_cfg = ../loop.cfg\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("loop.cfg"),
+            "[settings]\nimport_cfg = sub/imported.cfg\n",
+        )
+        .unwrap();
+
+        let mut report = Report::new();
+        let text = resolve_imports(
+            "[settings]\napi_key = base\napi_url = https://example.com\nimport_cfg = sub/imported.cfg\n"
+                .to_string(),
+            &dir,
+            &mut Vec::new(),
+            &mut report,
+        );
+        // sub/imported.cfg imports loop.cfg, which imports sub/imported.cfg again.
+        assert!(text.is_none());
+        assert_eq!(
+            report.last().unwrap().level,
+            crate::diagnostic::Level::Error
+        );
+        assert!(report.last().unwrap().message.contains("imports itself"));
+
+        std::fs::write(dir.join("loop.cfg"), "[settings]\ndebug = true\n").unwrap();
+        let mut report = Report::new();
+        let text = resolve_imports(
+            "[settings]\napi_key = base\napi_url = https://example.com\nimport_cfg = sub/imported.cfg\n"
+                .to_string(),
+            &dir,
+            &mut Vec::new(),
+            &mut report,
+        )
+        .unwrap();
+        let config = parse_config(&text).unwrap();
+        assert_eq!(config.settings.api_key, "imported");
+        assert_eq!(config.settings.api_url, "https://example.com");
+        assert!(is_enabled(&config.settings.debug));
+        assert_eq!(report.len(), 2);
+
+        std::fs::remove_file(dir.join("loop.cfg")).unwrap();
+        let mut report = Report::new();
+        assert!(
+            resolve_imports(
+                "[settings]\nimport_cfg = loop.cfg\n".to_string(),
+                &dir,
+                &mut Vec::new(),
+                &mut report,
+            )
+            .is_none()
+        );
+        assert!(report[0].message.starts_with("Cannot read import_cfg"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn applies_profiles() {
+        let text = "[settings]\napi_key = a\napi_url = https://example.com\n[settings.work]\napi_key = b\n[other]\nx = 1\n";
+        let (merged, source) = apply_profile(text, "work").unwrap();
+        assert_eq!(source, "[settings.work]");
+        let config = parse_config(&merged).unwrap();
+        assert_eq!(config.settings.api_key, "b");
+        assert_eq!(config.settings.api_url, "https://example.com");
+
+        let config = parse_config(text).unwrap();
+        assert_eq!(config.settings.api_key, "a");
+    }
+}
@@ -0,0 +1,762 @@
+//! Diagnostics produced by the checks.
+
+use std::{fmt::Display, str::FromStr, time::Duration};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize, de::IntoDeserializer};
+
+use crate::fix::Fix;
+
+/// Severity of a [`Diagnostic`], ordered from least to most severe.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    /// A check passed.
+    Ok,
+    /// Informational note.
+    Info,
+    /// Something is probably wrong, but might be intended.
+    Warning,
+    /// Something is definitely wrong.
+    Error,
+}
+
+impl Level {
+    /// Emoji prefix used for this level in human output.
+    pub fn emoji(self) -> &'static str {
+        match self {
+            Self::Ok => "✅",
+            Self::Info => "ℹ️",
+            Self::Warning => "⚠️",
+            Self::Error => "❌",
+        }
+    }
+
+    /// ASCII prefix used for this level in human output instead of [`Level::emoji`].
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Ok => "[OK]",
+            Self::Info => "[INFO]",
+            Self::Warning => "[WARN]",
+            Self::Error => "[FAIL]",
+        }
+    }
+
+    /// Whether this level means something is, or might be, wrong.
+    pub fn is_problem(self) -> bool {
+        matches!(self, Self::Warning | Self::Error)
+    }
+}
+
+/// Stable identifier of the check that produced a [`Diagnostic`], serialized as its
+/// [`DiagnosticCode::as_str`] name and explained by [`explanation`](crate::explain::explanation).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticCode {
+    /// Config directory set by WAKATIME_HOME.
+    WakatimeHome,
+    /// Unexpandable ~ in the config location.
+    ConfigPathExpand,
+    /// Config location that is a directory.
+    ConfigDir,
+    /// Path of the config file read.
+    ConfigPath,
+    /// Readable config file.
+    ConfigRead,
+    /// Config file that is not UTF-8.
+    ConfigNotUtf8,
+    /// Windows line endings in the config.
+    ConfigCrlf,
+    /// Empty config file.
+    ConfigEmpty,
+    /// Profile applied with --profile.
+    Profile,
+    /// Config file merged in by import_cfg.
+    ConfigImport,
+    /// Indented config lines.
+    ConfigIndented,
+    /// Key repeated in [settings].
+    ConfigDuplicateKey,
+    /// Missing [settings] section.
+    ConfigNoSettings,
+    /// Config parse result.
+    ConfigParse,
+    /// Environment references expanded with --expand-env.
+    EnvExpand,
+    /// Reference to an unset environment variable.
+    EnvUndefined,
+    /// API URL given with --api-url.
+    ApiUrlOverride,
+    /// Missing api_url.
+    ApiUrlMissing,
+    /// Valid api_url.
+    ApiUrlValid,
+    /// Unparsable api_url.
+    ApiUrlInvalid,
+    /// HTTPS api_url.
+    SchemeHttps,
+    /// Plain HTTP api_url.
+    SchemeHttp,
+    /// api_url scheme other than HTTP or HTTPS.
+    SchemeUnknown,
+    /// Known hosts file.
+    KnownHosts,
+    /// Kind of server given with --assume-host.
+    HostAssumed,
+    /// api_url without a host.
+    HostNull,
+    /// Hackatime host.
+    HostHackatime,
+    /// Old Hack Club Wakatime host.
+    HostOldHackatime,
+    /// Default Wakatime host.
+    HostWakatimeDefault,
+    /// Known custom host.
+    HostKnownCustom,
+    /// Unknown custom host.
+    HostCustom,
+    /// Website address used as api_url.
+    ApiUrlWebsite,
+    /// Expected API path.
+    PathOk,
+    /// Wrong API path.
+    PathWrong,
+    /// Retired old Hack Club Wakatime server.
+    OldHackatimeMigrate,
+    /// Unusual API path on a custom server.
+    PathUnusual,
+    /// Wakapi server.
+    Wakapi,
+    /// api_url ending in the heartbeats endpoint.
+    ApiUrlEndpoint,
+    /// Empty segment in the api_url path.
+    ApiUrlEmptySegment,
+    /// Trailing slash on api_url.
+    ApiUrlTrailingSlash,
+    /// URL heartbeats are sent to.
+    Endpoint,
+    /// API key given with --api-key.
+    KeyOverride,
+    /// Missing API key.
+    KeyMissing,
+    /// API key printed by api_key_vault_cmd.
+    VaultOk,
+    /// Failed api_key_vault_cmd.
+    VaultFailed,
+    /// Both api_key and api_key_vault_cmd set.
+    VaultDualKey,
+    /// api_key matching the vault key.
+    VaultDualKeyMatch,
+    /// api_key differing from the vault key.
+    VaultDualKeyMismatch,
+    /// API key read from the system keyring.
+    KeyKeyring,
+    /// Unreadable system keyring.
+    KeyringFailed,
+    /// Quoted or padded api_key.
+    KeyQuoted,
+    /// Well-formed API key.
+    KeyValid,
+    /// API key for the other server.
+    KeyCrossed,
+    /// Malformed API key.
+    KeyInvalid,
+    /// Boolean setting that is not true or false.
+    BoolInvalid,
+    /// Debug logging.
+    DebugEnabled,
+    /// Writable log_file.
+    LogFile,
+    /// Missing log_file directory.
+    LogFileDirMissing,
+    /// Unwritable log_file.
+    LogFileUnwritable,
+    /// Valid timeout.
+    Timeout,
+    /// Disabled timeout.
+    TimeoutZero,
+    /// Invalid timeout.
+    TimeoutInvalid,
+    /// Sensible heartbeat_rate_limit_seconds.
+    HeartbeatRateLimit,
+    /// Extreme heartbeat_rate_limit_seconds.
+    HeartbeatRateLimitExtreme,
+    /// Invalid heartbeat_rate_limit_seconds.
+    HeartbeatRateLimitInvalid,
+    /// Valid hostname setting.
+    Hostname,
+    /// Blank hostname setting.
+    HostnameEmpty,
+    /// Invalid hostname setting.
+    HostnameInvalid,
+    /// Valid exclude and include patterns.
+    Patterns,
+    /// Invalid exclude or include pattern.
+    PatternInvalid,
+    /// Enabled exclude_unknown_project.
+    ExcludeUnknownProject,
+    /// Enabled include_only_with_project_file.
+    IncludeOnlyWithProjectFile,
+    /// Disabled status bar.
+    StatusBarEnabled,
+    /// Hidden status bar coding time.
+    StatusBarCodingActivity,
+    /// Valid [projectmap] entries.
+    Projectmap,
+    /// Invalid [projectmap] entry.
+    ProjectmapInvalid,
+    /// Inconsistent privacy settings.
+    PrivacyInconsistent,
+    /// Config file permissions.
+    ConfigPermissions,
+    /// Backoff after failed heartbeats.
+    InternalBackoff,
+    /// Offline heartbeat queue.
+    OfflineQueue,
+    /// Latest wakadoctor release.
+    UpdateCheck,
+    /// Newer wakadoctor release.
+    UpdateAvailable,
+    /// Missing wakatime-cli.
+    CliMissing,
+    /// wakatime-cli version.
+    CliVersion,
+    /// Configured proxy.
+    Proxy,
+    /// Invalid proxy URL.
+    ProxyInvalid,
+    /// Disabled TLS certificate verification.
+    NoSslVerify,
+    /// Custom CA bundle.
+    SslCertsFile,
+    /// HTTP client setup failure.
+    ClientBuild,
+    /// Newest TLS version of the server.
+    TlsVersion,
+    /// Outdated TLS on the server.
+    TlsOutdated,
+    /// TLS version below --min-tls.
+    TlsBelowMinimum,
+    /// Offline mode.
+    Offline,
+    /// Dry run.
+    DryRun,
+    /// DNS resolution of the API host.
+    Dns,
+    /// Accepted API key.
+    UserOk,
+    /// Rejected API key.
+    UserRejected,
+    /// Missing users/current endpoint.
+    UserUnsupported,
+    /// HTML answer instead of JSON.
+    CaptivePortal,
+    /// Rate limited requests.
+    RateLimited,
+    /// Unverified API key after a probe.
+    HeartbeatProbeOnly,
+    /// Both heartbeat endpoints.
+    HeartbeatEndpoints,
+    /// Missing heartbeats.bulk endpoint.
+    HeartbeatBulkMissing,
+    /// Missing single heartbeats endpoint.
+    HeartbeatSingleMissing,
+    /// Missing heartbeat endpoints.
+    HeartbeatEndpointsMissing,
+    /// Skipped heartbeat check.
+    HeartbeatSkipped,
+    /// Retried heartbeat.
+    HeartbeatRetry,
+    /// Accepted test heartbeat.
+    HeartbeatOk,
+    /// Unexpected heartbeat status.
+    HeartbeatUnexpectedStatus,
+    /// Connection timeout.
+    HeartbeatConnectTimeout,
+    /// Response timeout.
+    HeartbeatTimeout,
+    /// Unsendable test heartbeat.
+    HeartbeatError,
+    /// Request latency.
+    HeartbeatLatency,
+    /// Shape of the heartbeat response.
+    HeartbeatResponse,
+    /// Clock skew against the server.
+    ClockSkew,
+    /// Removal of the test heartbeat.
+    HeartbeatCleanup,
+    /// Network checks past --deadline.
+    Deadline,
+    /// Working config.
+    Configured,
+}
+
+impl DiagnosticCode {
+    /// Every code, in the order the checks run.
+    pub const ALL: &[Self] = &[
+        Self::WakatimeHome,
+        Self::ConfigPathExpand,
+        Self::ConfigDir,
+        Self::ConfigPath,
+        Self::ConfigRead,
+        Self::ConfigNotUtf8,
+        Self::ConfigCrlf,
+        Self::ConfigEmpty,
+        Self::Profile,
+        Self::ConfigImport,
+        Self::ConfigIndented,
+        Self::ConfigDuplicateKey,
+        Self::ConfigNoSettings,
+        Self::ConfigParse,
+        Self::EnvExpand,
+        Self::EnvUndefined,
+        Self::ApiUrlOverride,
+        Self::ApiUrlMissing,
+        Self::ApiUrlValid,
+        Self::ApiUrlInvalid,
+        Self::SchemeHttps,
+        Self::SchemeHttp,
+        Self::SchemeUnknown,
+        Self::KnownHosts,
+        Self::HostAssumed,
+        Self::HostNull,
+        Self::HostHackatime,
+        Self::HostOldHackatime,
+        Self::HostWakatimeDefault,
+        Self::HostKnownCustom,
+        Self::HostCustom,
+        Self::ApiUrlWebsite,
+        Self::PathOk,
+        Self::PathWrong,
+        Self::OldHackatimeMigrate,
+        Self::PathUnusual,
+        Self::Wakapi,
+        Self::ApiUrlEndpoint,
+        Self::ApiUrlEmptySegment,
+        Self::ApiUrlTrailingSlash,
+        Self::Endpoint,
+        Self::KeyOverride,
+        Self::KeyMissing,
+        Self::VaultOk,
+        Self::VaultFailed,
+        Self::VaultDualKey,
+        Self::VaultDualKeyMatch,
+        Self::VaultDualKeyMismatch,
+        Self::KeyKeyring,
+        Self::KeyringFailed,
+        Self::KeyQuoted,
+        Self::KeyValid,
+        Self::KeyCrossed,
+        Self::KeyInvalid,
+        Self::BoolInvalid,
+        Self::DebugEnabled,
+        Self::LogFile,
+        Self::LogFileDirMissing,
+        Self::LogFileUnwritable,
+        Self::Timeout,
+        Self::TimeoutZero,
+        Self::TimeoutInvalid,
+        Self::HeartbeatRateLimit,
+        Self::HeartbeatRateLimitExtreme,
+        Self::HeartbeatRateLimitInvalid,
+        Self::Hostname,
+        Self::HostnameEmpty,
+        Self::HostnameInvalid,
+        Self::Patterns,
+        Self::PatternInvalid,
+        Self::ExcludeUnknownProject,
+        Self::IncludeOnlyWithProjectFile,
+        Self::StatusBarEnabled,
+        Self::StatusBarCodingActivity,
+        Self::Projectmap,
+        Self::ProjectmapInvalid,
+        Self::PrivacyInconsistent,
+        Self::ConfigPermissions,
+        Self::InternalBackoff,
+        Self::OfflineQueue,
+        Self::UpdateCheck,
+        Self::UpdateAvailable,
+        Self::CliMissing,
+        Self::CliVersion,
+        Self::Proxy,
+        Self::ProxyInvalid,
+        Self::NoSslVerify,
+        Self::SslCertsFile,
+        Self::ClientBuild,
+        Self::TlsVersion,
+        Self::TlsOutdated,
+        Self::TlsBelowMinimum,
+        Self::Offline,
+        Self::DryRun,
+        Self::Dns,
+        Self::UserOk,
+        Self::UserRejected,
+        Self::UserUnsupported,
+        Self::CaptivePortal,
+        Self::RateLimited,
+        Self::HeartbeatProbeOnly,
+        Self::HeartbeatEndpoints,
+        Self::HeartbeatBulkMissing,
+        Self::HeartbeatSingleMissing,
+        Self::HeartbeatEndpointsMissing,
+        Self::HeartbeatSkipped,
+        Self::HeartbeatRetry,
+        Self::HeartbeatOk,
+        Self::HeartbeatUnexpectedStatus,
+        Self::HeartbeatConnectTimeout,
+        Self::HeartbeatTimeout,
+        Self::HeartbeatError,
+        Self::HeartbeatLatency,
+        Self::HeartbeatResponse,
+        Self::ClockSkew,
+        Self::HeartbeatCleanup,
+        Self::Deadline,
+        Self::Configured,
+    ];
+
+    /// The name of this code, as printed in reports and accepted by `--only`, `--skip` and
+    /// `wakadoctor explain`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::WakatimeHome => "wakatime_home",
+            Self::ConfigPathExpand => "config_path_expand",
+            Self::ConfigDir => "config_dir",
+            Self::ConfigPath => "config_path",
+            Self::ConfigRead => "config_read",
+            Self::ConfigNotUtf8 => "config_not_utf8",
+            Self::ConfigCrlf => "config_crlf",
+            Self::ConfigEmpty => "config_empty",
+            Self::Profile => "profile",
+            Self::ConfigImport => "config_import",
+            Self::ConfigIndented => "config_indented",
+            Self::ConfigDuplicateKey => "config_duplicate_key",
+            Self::ConfigNoSettings => "config_no_settings",
+            Self::ConfigParse => "config_parse",
+            Self::EnvExpand => "env_expand",
+            Self::EnvUndefined => "env_undefined",
+            Self::ApiUrlOverride => "api_url_override",
+            Self::ApiUrlMissing => "api_url_missing",
+            Self::ApiUrlValid => "api_url_valid",
+            Self::ApiUrlInvalid => "api_url_invalid",
+            Self::SchemeHttps => "scheme_https",
+            Self::SchemeHttp => "scheme_http",
+            Self::SchemeUnknown => "scheme_unknown",
+            Self::KnownHosts => "known_hosts",
+            Self::HostAssumed => "host_assumed",
+            Self::HostNull => "host_null",
+            Self::HostHackatime => "host_hackatime",
+            Self::HostOldHackatime => "host_old_hackatime",
+            Self::HostWakatimeDefault => "host_wakatime_default",
+            Self::HostKnownCustom => "host_known_custom",
+            Self::HostCustom => "host_custom",
+            Self::ApiUrlWebsite => "api_url_website",
+            Self::PathOk => "path_ok",
+            Self::PathWrong => "path_wrong",
+            Self::OldHackatimeMigrate => "old_hackatime_migrate",
+            Self::PathUnusual => "path_unusual",
+            Self::Wakapi => "wakapi",
+            Self::ApiUrlEndpoint => "api_url_endpoint",
+            Self::ApiUrlEmptySegment => "api_url_empty_segment",
+            Self::ApiUrlTrailingSlash => "api_url_trailing_slash",
+            Self::Endpoint => "endpoint",
+            Self::KeyOverride => "key_override",
+            Self::KeyMissing => "key_missing",
+            Self::VaultOk => "vault_ok",
+            Self::VaultFailed => "vault_failed",
+            Self::VaultDualKey => "vault_dual_key",
+            Self::VaultDualKeyMatch => "vault_dual_key_match",
+            Self::VaultDualKeyMismatch => "vault_dual_key_mismatch",
+            Self::KeyKeyring => "key_keyring",
+            Self::KeyringFailed => "keyring_failed",
+            Self::KeyQuoted => "key_quoted",
+            Self::KeyValid => "key_valid",
+            Self::KeyCrossed => "key_crossed",
+            Self::KeyInvalid => "key_invalid",
+            Self::BoolInvalid => "bool_invalid",
+            Self::DebugEnabled => "debug_enabled",
+            Self::LogFile => "log_file",
+            Self::LogFileDirMissing => "log_file_dir_missing",
+            Self::LogFileUnwritable => "log_file_unwritable",
+            Self::Timeout => "timeout",
+            Self::TimeoutZero => "timeout_zero",
+            Self::TimeoutInvalid => "timeout_invalid",
+            Self::HeartbeatRateLimit => "heartbeat_rate_limit",
+            Self::HeartbeatRateLimitExtreme => "heartbeat_rate_limit_extreme",
+            Self::HeartbeatRateLimitInvalid => "heartbeat_rate_limit_invalid",
+            Self::Hostname => "hostname",
+            Self::HostnameEmpty => "hostname_empty",
+            Self::HostnameInvalid => "hostname_invalid",
+            Self::Patterns => "patterns",
+            Self::PatternInvalid => "pattern_invalid",
+            Self::ExcludeUnknownProject => "exclude_unknown_project",
+            Self::IncludeOnlyWithProjectFile => "include_only_with_project_file",
+            Self::StatusBarEnabled => "status_bar_enabled",
+            Self::StatusBarCodingActivity => "status_bar_coding_activity",
+            Self::Projectmap => "projectmap",
+            Self::ProjectmapInvalid => "projectmap_invalid",
+            Self::PrivacyInconsistent => "privacy_inconsistent",
+            Self::ConfigPermissions => "config_permissions",
+            Self::InternalBackoff => "internal_backoff",
+            Self::OfflineQueue => "offline_queue",
+            Self::UpdateCheck => "update_check",
+            Self::UpdateAvailable => "update_available",
+            Self::CliMissing => "cli_missing",
+            Self::CliVersion => "cli_version",
+            Self::Proxy => "proxy",
+            Self::ProxyInvalid => "proxy_invalid",
+            Self::NoSslVerify => "no_ssl_verify",
+            Self::SslCertsFile => "ssl_certs_file",
+            Self::ClientBuild => "client_build",
+            Self::TlsVersion => "tls_version",
+            Self::TlsOutdated => "tls_outdated",
+            Self::TlsBelowMinimum => "tls_below_minimum",
+            Self::Offline => "offline",
+            Self::DryRun => "dry_run",
+            Self::Dns => "dns",
+            Self::UserOk => "user_ok",
+            Self::UserRejected => "user_rejected",
+            Self::UserUnsupported => "user_unsupported",
+            Self::CaptivePortal => "captive_portal",
+            Self::RateLimited => "rate_limited",
+            Self::HeartbeatProbeOnly => "heartbeat_probe_only",
+            Self::HeartbeatEndpoints => "heartbeat_endpoints",
+            Self::HeartbeatBulkMissing => "heartbeat_bulk_missing",
+            Self::HeartbeatSingleMissing => "heartbeat_single_missing",
+            Self::HeartbeatEndpointsMissing => "heartbeat_endpoints_missing",
+            Self::HeartbeatSkipped => "heartbeat_skipped",
+            Self::HeartbeatRetry => "heartbeat_retry",
+            Self::HeartbeatOk => "heartbeat_ok",
+            Self::HeartbeatUnexpectedStatus => "heartbeat_unexpected_status",
+            Self::HeartbeatConnectTimeout => "heartbeat_connect_timeout",
+            Self::HeartbeatTimeout => "heartbeat_timeout",
+            Self::HeartbeatError => "heartbeat_error",
+            Self::HeartbeatLatency => "heartbeat_latency",
+            Self::HeartbeatResponse => "heartbeat_response",
+            Self::ClockSkew => "clock_skew",
+            Self::HeartbeatCleanup => "heartbeat_cleanup",
+            Self::Deadline => "deadline",
+            Self::Configured => "configured",
+        }
+    }
+}
+
+impl Display for DiagnosticCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+impl FromStr for DiagnosticCode {
+    type Err = serde::de::value::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(s.into_deserializer())
+    }
+}
+
+/// A single finding produced by the checks.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, JsonSchema)]
+pub struct Diagnostic {
+    /// Severity of the finding.
+    pub level: Level,
+    /// Stable identifier of the check that produced the finding.
+    pub code: DiagnosticCode,
+    /// Human readable description of the finding.
+    pub message: String,
+    /// Automatic repair of the config file for this finding, if there is one.
+    #[serde(skip)]
+    pub fix: Option<Fix>,
+    /// Round-trip time of the request the finding is about, in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+}
+
+impl Diagnostic {
+    /// Create a diagnostic with the given level, code and message.
+    pub fn new(level: Level, code: DiagnosticCode, message: impl Into<String>) -> Self {
+        Self {
+            level,
+            code,
+            message: message.into(),
+            fix: None,
+            latency_ms: None,
+        }
+    }
+
+    /// Attach an automatic repair to this diagnostic.
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+
+    /// Record the round-trip time of the request this diagnostic is about.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency_ms = Some(latency.as_millis().try_into().unwrap_or(u64::MAX));
+        self
+    }
+
+    /// Create a [`Level::Ok`] diagnostic.
+    pub fn ok(code: DiagnosticCode, message: impl Into<String>) -> Self {
+        Self::new(Level::Ok, code, message)
+    }
+
+    /// Create a [`Level::Info`] diagnostic.
+    pub fn info(code: DiagnosticCode, message: impl Into<String>) -> Self {
+        Self::new(Level::Info, code, message)
+    }
+
+    /// Create a [`Level::Warning`] diagnostic.
+    pub fn warning(code: DiagnosticCode, message: impl Into<String>) -> Self {
+        Self::new(Level::Warning, code, message)
+    }
+
+    /// Create a [`Level::Error`] diagnostic.
+    pub fn error(code: DiagnosticCode, message: impl Into<String>) -> Self {
+        Self::new(Level::Error, code, message)
+    }
+}
+
+impl Diagnostic {
+    /// Format this diagnostic as a line of human output, prefixed with [`Level::label`] if
+    /// `ascii` or [`Level::emoji`] otherwise.
+    pub fn line(&self, ascii: bool) -> String {
+        let prefix = if ascii {
+            self.level.label()
+        } else {
+            self.level.emoji()
+        };
+        format!("{prefix} [{}] - {}", self.code, self.message)
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.line(false))
+    }
+}
+
+/// All diagnostics produced by a run, in the order they were produced.
+pub type Report = Vec<Diagnostic>;
+
+/// Counts of diagnostics by level.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, JsonSchema)]
+pub struct Summary {
+    /// Number of passed checks.
+    pub passed: usize,
+    /// Number of informational notes.
+    pub info: usize,
+    /// Number of warnings.
+    pub warnings: usize,
+    /// Number of errors.
+    pub errors: usize,
+}
+
+impl Summary {
+    /// Count the diagnostics in `report`.
+    pub fn of(report: &Report) -> Self {
+        let mut summary = Self::default();
+        for diagnostic in report {
+            match diagnostic.level {
+                Level::Ok => summary.passed += 1,
+                Level::Info => summary.info += 1,
+                Level::Warning => summary.warnings += 1,
+                Level::Error => summary.errors += 1,
+            }
+        }
+        summary
+    }
+
+    /// Overall status: the most severe level of a warning or error, otherwise [`Level::Ok`].
+    pub fn level(&self) -> Level {
+        if self.errors > 0 {
+            Level::Error
+        } else if self.warnings > 0 {
+            Level::Warning
+        } else {
+            Level::Ok
+        }
+    }
+
+    /// Whether there were no errors.
+    pub fn ok(&self) -> bool {
+        self.errors == 0
+    }
+
+    /// Process exit code for this summary: 0 if everything passed, 1 if there were errors, and 2
+    /// if there were only warnings (1 if `strict`).
+    pub fn exit_code(&self, strict: bool) -> u8 {
+        match self.level() {
+            Level::Error => 1,
+            Level::Warning if strict => 1,
+            Level::Warning => 2,
+            _ => 0,
+        }
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Summary: {} passed, {} warning{}, {} error{}",
+            self.passed,
+            self.warnings,
+            if self.warnings == 1 { "" } else { "s" },
+            self.errors,
+            if self.errors == 1 { "" } else { "s" },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_counts_and_exit_codes() {
+        let mut report = vec![
+            Diagnostic::ok(DiagnosticCode::ConfigRead, "a"),
+            Diagnostic::info(DiagnosticCode::ConfigPath, "b"),
+            Diagnostic::warning(DiagnosticCode::KeyQuoted, "c"),
+        ];
+        let summary = Summary::of(&report);
+        assert_eq!(
+            summary.to_string(),
+            "Summary: 1 passed, 1 warning, 0 errors"
+        );
+        assert_eq!(summary.level(), Level::Warning);
+        assert_eq!((summary.exit_code(false), summary.exit_code(true)), (2, 1));
+
+        report.push(Diagnostic::error(DiagnosticCode::KeyMissing, "d"));
+        assert_eq!(Summary::of(&report).exit_code(false), 1);
+        assert_eq!(Summary::of(&report[..2].to_vec()).exit_code(true), 0);
+    }
+
+    #[test]
+    fn codes_round_trip() {
+        for (i, code) in DiagnosticCode::ALL.iter().enumerate() {
+            assert_eq!(code.as_str().parse::<DiagnosticCode>().ok(), Some(*code));
+            assert_eq!(serde_json::to_value(code).unwrap(), code.as_str());
+            assert!(
+                !DiagnosticCode::ALL[..i].contains(code),
+                "{code} is listed twice"
+            );
+        }
+        assert!("not_a_code".parse::<DiagnosticCode>().is_err());
+    }
+
+    #[test]
+    fn all_codes_listed() {
+        // The schema is derived from the enum itself, so it has every variant.
+        let schema = serde_json::to_value(schemars::schema_for!(DiagnosticCode)).unwrap();
+        let variants = schema["oneOf"].as_array().unwrap();
+        for variant in variants {
+            let code = variant["const"].as_str().unwrap();
+            assert!(
+                DiagnosticCode::ALL.iter().any(|v| v.as_str() == code),
+                "{code} is missing from DiagnosticCode::ALL"
+            );
+        }
+        assert_eq!(DiagnosticCode::ALL.len(), variants.len());
+    }
+}
@@ -0,0 +1,81 @@
+//! Resolving the API host.
+
+use std::time::Duration;
+
+use url::{Host, Url};
+
+use crate::{
+    client::IpVersion,
+    diagnostic::{Diagnostic, DiagnosticCode, Report},
+};
+
+/// Diagnostic codes the DNS check can report.
+pub const CODES: &[DiagnosticCode] = &[DiagnosticCode::Dns];
+
+/// Resolve the host of `url` to addresses of `ip_version`. Returns whether it resolved.
+///
+/// IP address hosts need no resolving and always succeed.
+pub async fn check_dns(
+    url: &Url,
+    timeout: Duration,
+    ip_version: IpVersion,
+    report: &mut Report,
+) -> bool {
+    let Some(Host::Domain(name)) = url.host() else {
+        return true;
+    };
+    let port = url.port_or_known_default().unwrap_or(443);
+
+    let addrs = match tokio::time::timeout(timeout, tokio::net::lookup_host((name, port))).await {
+        Ok(Ok(v)) => v.map(|v| v.ip()).collect::<Vec<_>>(),
+        Ok(Err(e)) => {
+            report.push(Diagnostic::error(
+                DiagnosticCode::Dns,
+                format!("Could not resolve host {name}: {e}"),
+            ));
+            return false;
+        }
+        Err(_) => {
+            report.push(Diagnostic::error(
+                DiagnosticCode::Dns,
+                format!(
+                    "Could not resolve host {name}: timed out after {} seconds",
+                    timeout.as_secs()
+                ),
+            ));
+            return false;
+        }
+    };
+
+    let total = addrs.len();
+    let addrs = addrs
+        .into_iter()
+        .filter(|v| ip_version.allows(v))
+        .collect::<Vec<_>>();
+    if addrs.is_empty() && total > 0 {
+        report.push(Diagnostic::error(
+            DiagnosticCode::Dns,
+            format!(
+                "Host {name} has {total} address(es), but none for {ip_version} (--ip-version)"
+            ),
+        ));
+        return false;
+    }
+    let Some(first) = addrs.first() else {
+        report.push(Diagnostic::error(
+            DiagnosticCode::Dns,
+            format!("Could not resolve host {name}: no addresses found"),
+        ));
+        return false;
+    };
+    let more = if addrs.len() > 1 {
+        format!(" (+{} more)", addrs.len() - 1)
+    } else {
+        String::new()
+    };
+    report.push(Diagnostic::ok(
+        DiagnosticCode::Dns,
+        format!("Resolved {name} to {first}{more}"),
+    ));
+    true
+}
@@ -0,0 +1,135 @@
+//! Listing the network requests the checks would make, for `--dry-run`.
+
+use url::Url;
+
+use crate::{
+    bulk,
+    checks::CheckOptions,
+    client,
+    diagnostic::{Diagnostic, DiagnosticCode, Report},
+    heartbeat::{self, endpoint},
+    host::WakaHost,
+    redact, update, wakapi,
+};
+
+/// Push an info to `report` for every request the network checks would make for `url` on `host`
+/// with `api_key`, in the order they would be made.
+///
+/// `https` and `proxied` are whether `url` uses https and requests go through a proxy, as worked
+/// out by the checks.
+pub fn plan_requests(
+    url: Option<&Url>,
+    host: Option<WakaHost>,
+    https: bool,
+    api_key: Option<&str>,
+    proxied: bool,
+    opts: &CheckOptions,
+    report: &mut Report,
+) {
+    let mut plan = |message: String| report.push(Diagnostic::info(DiagnosticCode::DryRun, message));
+    plan("Dry run: listing network requests instead of sending them".to_string());
+    plan(format!(
+        "Would send User-Agent: {} with every request",
+        opts.user_agent
+            .clone()
+            .unwrap_or_else(client::default_user_agent)
+    ));
+
+    if let Some(url) = url {
+        if !proxied && let Some(name) = url.host_str() {
+            plan(format!("Would resolve {name} to check DNS"));
+        }
+        if https {
+            plan(format!(
+                "Would send HEAD {url} up to 3 times to find the TLS version"
+            ));
+        }
+        if host == Some(WakaHost::Custom) {
+            plan(format!(
+                "Would send GET {} to detect wakapi",
+                wakapi::health_url(url)
+            ));
+        }
+    }
+
+    if let (Some(url), Some(host), true, Some(api_key)) = (url, host, https, api_key) {
+        let auth = format!("Authorization: Bearer {}", redact::redact(api_key));
+        if host == WakaHost::Custom {
+            for path in [bulk::SINGLE_PATH, bulk::BULK_PATH] {
+                plan(format!(
+                    "Would send POST {} with {auth} and an empty batch to check the endpoint exists",
+                    endpoint(url, path)
+                ));
+            }
+        }
+        plan(format!(
+            "Would send GET {} with {auth}",
+            endpoint(url, "users/current")
+        ));
+        if opts.send_real_heartbeat {
+            plan(format!(
+                "Would send POST {} with {auth} and a test heartbeat for {}",
+                endpoint(url, "users/current/heartbeats"),
+                opts.heartbeat_entity
+                    .as_deref()
+                    .unwrap_or(heartbeat::DEFAULT_ENTITY)
+            ));
+            if !opts.no_cleanup {
+                plan(format!(
+                    "Would send DELETE {} with {auth} to remove the test heartbeat",
+                    endpoint(url, "users/current/heartbeats.bulk")
+                ));
+            }
+        }
+    } else {
+        plan("Would send no API requests because of earlier errors".to_string());
+    }
+
+    if opts.check_update {
+        plan(format!("Would send GET {}", update::CRATES_IO_URL));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plans_requests() {
+        let url = Url::parse("https://wakapi.example.com/api").unwrap();
+        let opts = CheckOptions {
+            send_real_heartbeat: true,
+            user_agent: Some("test".to_string()),
+            ..Default::default()
+        };
+        let mut report = Report::new();
+        plan_requests(
+            Some(&url),
+            Some(WakaHost::Custom),
+            true,
+            Some("3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e"),
+            false,
+            &opts,
+            &mut report,
+        );
+
+        assert_eq!(
+            report
+                .iter()
+                .map(|v| v.message.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "Dry run: listing network requests instead of sending them",
+                "Would send User-Agent: test with every request",
+                "Would resolve wakapi.example.com to check DNS",
+                "Would send HEAD https://wakapi.example.com/api up to 3 times to find the TLS version",
+                "Would send GET https://wakapi.example.com/api/health to detect wakapi",
+                "Would send POST https://wakapi.example.com/api/users/current/heartbeats with Authorization: Bearer 3f1c3c3e**** and an empty batch to check the endpoint exists",
+                "Would send POST https://wakapi.example.com/api/users/current/heartbeats.bulk with Authorization: Bearer 3f1c3c3e**** and an empty batch to check the endpoint exists",
+                "Would send GET https://wakapi.example.com/api/users/current with Authorization: Bearer 3f1c3c3e****",
+                "Would send POST https://wakapi.example.com/api/users/current/heartbeats with Authorization: Bearer 3f1c3c3e**** and a test heartbeat for wakadoctor-test.txt",
+                "Would send DELETE https://wakapi.example.com/api/users/current/heartbeats.bulk with Authorization: Bearer 3f1c3c3e**** to remove the test heartbeat",
+            ]
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! The effective settings after every override, printed by `--dump-config`.
+
+use serde::Serialize;
+
+use crate::{
+    checks::CheckOptions,
+    config::{self, WakaConfig},
+    diagnostic::Report,
+    heartbeat, redact, vault,
+};
+
+/// Where the value of an [`EffectiveSetting`] came from, in order of precedence.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    /// A command line flag.
+    CommandLine,
+    /// The output of `api_key_vault_cmd`.
+    VaultCommand,
+    /// The system keyring, with the `keyring` feature.
+    Keyring,
+    /// The config file, with the profile and `--expand-env` applied.
+    Config,
+    /// The built-in default.
+    Default,
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::CommandLine => "command line",
+            Self::VaultCommand => "vault command",
+            Self::Keyring => "keyring",
+            Self::Config => "config",
+            Self::Default => "default",
+        })
+    }
+}
+
+/// A setting with the value the checks use.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+pub struct EffectiveSetting {
+    /// Name of the setting in the config.
+    pub key: &'static str,
+    /// Value of the setting, with secrets redacted unless asked otherwise.
+    pub value: String,
+    /// Where the value came from.
+    pub source: Source,
+}
+
+impl EffectiveSetting {
+    /// Create a setting with the given key, value and source.
+    fn new(key: &'static str, value: impl Into<String>, source: Source) -> Self {
+        Self {
+            key,
+            value: value.into(),
+            source,
+        }
+    }
+}
+
+/// Pick the value of `key` from the command line `flag` or the config `value`, falling back to
+/// `default`.
+fn pick(key: &'static str, flag: Option<String>, value: &str, default: &str) -> EffectiveSetting {
+    match flag {
+        Some(v) => EffectiveSetting::new(key, v, Source::CommandLine),
+        None if !value.trim().is_empty() => {
+            EffectiveSetting::new(key, value.trim(), Source::Config)
+        }
+        None => EffectiveSetting::new(key, default, Source::Default),
+    }
+}
+
+/// The boolean setting `key` as the checks see `value`, which is only set in the config.
+fn flag(key: &'static str, value: &str) -> EffectiveSetting {
+    let source = if value.trim().is_empty() {
+        Source::Default
+    } else {
+        Source::Config
+    };
+    EffectiveSetting::new(key, config::is_enabled(value).to_string(), source)
+}
+
+/// Work out the settings the checks use for `config` with `opts`, running the vault command if
+/// needed.
+///
+/// The API key is redacted unless `show_secrets`.
+pub async fn effective_settings(
+    config: &WakaConfig,
+    opts: &CheckOptions,
+    show_secrets: bool,
+) -> Vec<EffectiveSetting> {
+    let mut settings = config.settings.clone();
+    if opts.expand_env {
+        config::expand_env(&mut settings, &mut Report::new());
+    }
+
+    let mut api_key = pick("api_key", opts.api_key.clone(), &settings.api_key, "");
+    let mut secret = !api_key.value.is_empty();
+    // wakatime prefers the vault command over api_key when both are set.
+    if api_key.source != Source::CommandLine && !settings.api_key_vault_cmd.is_empty() {
+        api_key =
+            match vault::run_vault_cmd(&settings.api_key_vault_cmd, vault::VAULT_TIMEOUT).await {
+                Ok(v) => {
+                    secret = true;
+                    EffectiveSetting::new("api_key", v, Source::VaultCommand)
+                }
+                Err(e) => EffectiveSetting::new("api_key", format!("({e})"), Source::VaultCommand),
+            };
+    }
+    #[cfg(feature = "keyring")]
+    if api_key.source == Source::Default
+        && settings.api_key_vault_cmd.is_empty()
+        && let Ok(Some(v)) = crate::keychain::read_key()
+    {
+        secret = true;
+        api_key = EffectiveSetting::new("api_key", v, Source::Keyring);
+    }
+    if secret && !show_secrets {
+        api_key.value = redact::redact(&api_key.value);
+    }
+
+    let mut proxy = pick("proxy", opts.proxy.clone(), &settings.proxy, "");
+    if !show_secrets
+        && let Ok(mut url) = url::Url::parse(&proxy.value)
+        && url.password().is_some()
+    {
+        let _ = url.set_password(Some("****"));
+        proxy.value = url.to_string();
+    }
+
+    vec![
+        pick(
+            "api_url",
+            opts.api_url.clone(),
+            &settings.api_url,
+            "https://api.wakatime.com/api/v1",
+        ),
+        api_key,
+        pick("api_key_vault_cmd", None, &settings.api_key_vault_cmd, ""),
+        proxy,
+        pick(
+            "timeout",
+            opts.timeout.map(|v| v.to_string()),
+            &settings.timeout,
+            &heartbeat::DEFAULT_TIMEOUT.to_string(),
+        ),
+        pick(
+            "heartbeat_rate_limit_seconds",
+            None,
+            &settings.heartbeat_rate_limit_seconds,
+            "120",
+        ),
+        flag("no_ssl_verify", &settings.no_ssl_verify),
+        pick("ssl_certs_file", None, &settings.ssl_certs_file, ""),
+        pick(
+            "hostname",
+            None,
+            settings.hostname.as_deref().unwrap_or_default(),
+            "",
+        ),
+        flag("debug", &settings.debug),
+        pick("log_file", None, &settings.log_file, ""),
+        flag("hide_file_names", &settings.hide_file_names),
+        flag("hide_project_names", &settings.hide_project_names),
+        flag("hide_branch_names", &settings.hide_branch_names),
+        flag("hide_dependencies", &settings.hide_dependencies),
+        flag("hide_project_folder", &settings.hide_project_folder),
+        pick(
+            "exclude",
+            None,
+            &config::value_lines(&settings.exclude).join(", "),
+            "",
+        ),
+        pick(
+            "include",
+            None,
+            &config::value_lines(&settings.include).join(", "),
+            "",
+        ),
+        pick(
+            "exclude_unknown_project",
+            None,
+            &settings.exclude_unknown_project,
+            "false",
+        ),
+        pick(
+            "include_only_with_project_file",
+            None,
+            &settings.include_only_with_project_file,
+            "false",
+        ),
+        pick(
+            "status_bar_enabled",
+            None,
+            &settings.status_bar_enabled,
+            "true",
+        ),
+        pick(
+            "status_bar_coding_activity",
+            None,
+            &settings.status_bar_coding_activity,
+            "true",
+        ),
+    ]
+}
+
+/// Render `settings` as an aligned table of key, value and source.
+pub fn render_human(settings: &[EffectiveSetting]) -> String {
+    let key_width = settings.iter().map(|v| v.key.len()).max().unwrap_or(0);
+    let value_width = settings
+        .iter()
+        .map(|v| v.value.chars().count())
+        .max()
+        .unwrap_or(0);
+    settings
+        .iter()
+        .map(|v| {
+            format!(
+                "{:key_width$}  {:value_width$}  ({})\n",
+                v.key, v.value, v.source
+            )
+        })
+        .collect()
+}
+
+/// Render `settings` as a JSON array.
+pub fn render_json(settings: &[EffectiveSetting]) -> String {
+    serde_json::to_string_pretty(settings).unwrap()
+}
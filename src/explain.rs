@@ -0,0 +1,542 @@
+//! Longer descriptions of diagnostic codes, printed by `wakadoctor explain`.
+
+use crate::diagnostic::DiagnosticCode;
+
+/// A paragraph describing what the diagnostic `code` means, why it fails and how to fix it.
+pub fn explanation(code: DiagnosticCode) -> &'static str {
+    match code {
+        DiagnosticCode::WakatimeHome => {
+            "The WAKATIME_HOME environment variable is set, so the config is looked for in that \
+             directory instead of your home directory, just like wakatime-cli does. Unset it if \
+             that is not what you want."
+        }
+        DiagnosticCode::ConfigPathExpand => {
+            "The config location starts with ~ but your home directory could not be determined. \
+             Pass an absolute path with --config-location."
+        }
+        DiagnosticCode::ConfigDir => {
+            "The config location is a directory, so wakadoctor looks for .wakatime.cfg or \
+             wakatime.cfg inside it. This fails if neither exists; pass the path of the file \
+             itself with --config-location."
+        }
+        DiagnosticCode::ConfigPath => {
+            "The absolute path of the config file that is read, after expanding ~ and \
+             WAKATIME_HOME and looking inside directories. If it is not the file you edited, pass \
+             yours with --config-location."
+        }
+        DiagnosticCode::ConfigRead => {
+            "The config file could be read. This fails if the file does not exist or is not \
+             readable; wakatime-cli creates ~/.wakatime.cfg the first time an editor plugin asks \
+             for your API key, or you can create it yourself."
+        }
+        DiagnosticCode::ConfigNotUtf8 => {
+            "The config file is not valid UTF-8, usually because an editor saved it in another \
+             encoding such as Latin-1. wakadoctor replaces the invalid bytes and carries on, so \
+             values with non-ASCII characters, such as paths, may not match what wakatime reads. \
+             Save the file as UTF-8."
+        }
+        DiagnosticCode::ConfigCrlf => {
+            "The config has Windows (CRLF) line endings, typically because it was edited on \
+             Windows and copied over. wakadoctor and wakatime-cli cope, but other tools reading \
+             the config may keep a stray \\r at the end of each value. Convert it with dos2unix if \
+             in doubt."
+        }
+        DiagnosticCode::ConfigEmpty => {
+            "The config file is empty or only whitespace, so wakatime has no API key or any other \
+             setting. Add a [settings] section with at least api_key."
+        }
+        DiagnosticCode::Profile => {
+            "--profile applies the values of a profile over [settings], key by key. The profile is \
+             a [settings.<name>] section of the config, or the [settings] section of \
+             ~/.config/wakadoctor/profiles/<name>.cfg. This fails if neither exists."
+        }
+        DiagnosticCode::ConfigImport => {
+            "import_cfg names another config file, whose [settings] are merged over those of the \
+             importing file, as wakatime-cli does. Relative paths are relative to the importing \
+             file. This fails if the imported file cannot be read, or if files import each other \
+             in a cycle."
+        }
+        DiagnosticCode::ConfigIndented => {
+            "Some key = value lines are indented. INI keys must start at column 0: an indented \
+             line is read as a continuation of the value above it, so the key is silently lost and \
+             the value above gets garbage appended. Remove the indentation."
+        }
+        DiagnosticCode::ConfigDuplicateKey => {
+            "A key appears more than once in [settings]. Only the last value is used, which is \
+             easy to miss when editing; remove the other lines."
+        }
+        DiagnosticCode::ConfigNoSettings => {
+            "The config has no [settings] section. wakatime only reads keys under [settings], so \
+             keys at the top of the file are ignored. Add a [settings] line above them, which \
+             --fix can do for you."
+        }
+        DiagnosticCode::ConfigParse => {
+            "The config file is valid INI with valid values. This fails on malformed lines or \
+             values of the wrong type, such as a boolean that is neither true nor false; the error \
+             names the offending key."
+        }
+        DiagnosticCode::EnvExpand => {
+            "--expand-env replaced ${VAR} or $VAR references in a config value with the values of \
+             those environment variables. wakatime-cli does not do this, so the config only works \
+             with tools that expand variables themselves."
+        }
+        DiagnosticCode::EnvUndefined => {
+            "A config value references an environment variable that is not set, so the reference \
+             was left as it is. Set the variable, or write $$ for a literal $."
+        }
+        DiagnosticCode::ApiUrlOverride => {
+            "The API URL given with --api-url is tested instead of api_url from the config."
+        }
+        DiagnosticCode::ApiUrlMissing => {
+            "The config has no api_url, so wakatime sends heartbeats to the default Wakatime API. \
+             Set api_url if you use Hackatime or another server."
+        }
+        DiagnosticCode::ApiUrlValid => {
+            "api_url parses as a URL. When it does not, the error says why; a common mistake is \
+             leaving out the https:// prefix."
+        }
+        DiagnosticCode::ApiUrlInvalid => {
+            "api_url does not parse as a URL, so wakatime cannot send any heartbeats. Check for \
+             typos and make sure it starts with https://."
+        }
+        DiagnosticCode::SchemeHttps => {
+            "api_url uses https, so heartbeats are encrypted in transit."
+        }
+        DiagnosticCode::SchemeHttp => {
+            "api_url uses plain http, which sends your API key unencrypted and is rejected by most \
+             servers. Change it to https, which --fix can do for you."
+        }
+        DiagnosticCode::SchemeUnknown => {
+            "api_url uses a scheme other than http or https, which wakatime cannot send heartbeats \
+             to. Change it to https."
+        }
+        DiagnosticCode::KnownHosts => {
+            "The known hosts file lists custom servers you trust, so they are reported as known \
+             instead of warned about. This fails if the file cannot be read or written; \
+             --known-hosts picks another file."
+        }
+        DiagnosticCode::HostAssumed => {
+            "--assume-host was passed, so the host of api_url is not classified and the path and \
+             key format rules of the given kind of server apply."
+        }
+        DiagnosticCode::HostNull => {
+            "api_url has no host, as in mailto: or file: URLs, so there is nowhere to send \
+             heartbeats. Use the full https:// URL of your server."
+        }
+        DiagnosticCode::HostHackatime => "api_url points at Hackatime.",
+        DiagnosticCode::HostOldHackatime => {
+            "api_url points at the old Hack Club Wakatime server, which has been replaced by \
+             Hackatime. See old_hackatime_migrate."
+        }
+        DiagnosticCode::HostWakatimeDefault => {
+            "api_url points at the default Wakatime server. If you meant to use Hackatime, set \
+             api_url to the Hackatime API URL; otherwise pass --no-warn-default-waka."
+        }
+        DiagnosticCode::HostKnownCustom => {
+            "api_url points at a custom server listed in your known hosts file."
+        }
+        DiagnosticCode::HostCustom => {
+            "api_url points at a server other than Wakatime or Hackatime. This is fine if you run \
+             your own server; pass --custom-server, or --remember-host to stop being warned about \
+             this host."
+        }
+        DiagnosticCode::ApiUrlWebsite => {
+            "api_url is the address of the Wakatime or Hackatime website you open in a browser, \
+             not of the API wakatime sends heartbeats to. Use the API URL shown in the message; \
+             --fix sets it for you."
+        }
+        DiagnosticCode::PathOk => "The path of api_url is the one the server expects.",
+        DiagnosticCode::PathWrong => {
+            "The path of api_url is not the one the server expects, so heartbeats go to an \
+             endpoint that does not exist. The message gives the right path, and --fix can set it \
+             for you."
+        }
+        DiagnosticCode::OldHackatimeMigrate => {
+            "The old Hack Club Wakatime server no longer accepts heartbeats. Set api_url to the \
+             Hackatime API URL, which --fix can do for you."
+        }
+        DiagnosticCode::PathUnusual => {
+            "The path of api_url on your custom server is not one wakatime-compatible servers \
+             usually use. Check it against your server's documentation, or pass --custom-server if \
+             it is right."
+        }
+        DiagnosticCode::Wakapi => {
+            "The custom server identified itself as wakapi, so its API path can be checked. wakapi \
+             accepts /api and /api/compat/wakatime/v1, after the path it is served under."
+        }
+        DiagnosticCode::ApiUrlEndpoint => {
+            "api_url already ends in the heartbeats endpoint. wakatime appends the endpoint \
+             itself, so heartbeats would go to the endpoint twice over. Remove it, leaving the API \
+             base URL."
+        }
+        DiagnosticCode::ApiUrlEmptySegment => {
+            "The path of api_url contains //, which some servers treat as a different path and \
+             reject. Remove the extra slash."
+        }
+        DiagnosticCode::ApiUrlTrailingSlash => {
+            "api_url ends with a slash. wakatime handles this, but some other clients do not, so \
+             remove it to be safe."
+        }
+        DiagnosticCode::Endpoint => {
+            "The full URL heartbeats are sent to, built from api_url. Check it against the \
+             heartbeats endpoint your server documents."
+        }
+        DiagnosticCode::KeyOverride => {
+            "The API key given with --api-key is tested instead of the one in the config."
+        }
+        DiagnosticCode::KeyMissing => {
+            "The config has neither api_key nor api_key_vault_cmd, so heartbeats cannot be \
+             authenticated. Copy your API key from your server's settings page into api_key."
+        }
+        DiagnosticCode::VaultOk => "api_key_vault_cmd ran successfully and printed an API key.",
+        DiagnosticCode::VaultFailed => {
+            "api_key_vault_cmd could not be run or exited with an error, so wakatime has no API \
+             key. Run the command yourself to see what goes wrong."
+        }
+        DiagnosticCode::VaultDualKey => {
+            "Both api_key and api_key_vault_cmd are set. wakatime only uses the vault command, so \
+             remove api_key to avoid confusion."
+        }
+        DiagnosticCode::VaultDualKeyMatch => {
+            "api_key is the same key the vault command prints, so it is harmless but redundant."
+        }
+        DiagnosticCode::VaultDualKeyMismatch => {
+            "api_key differs from the key the vault command prints. wakatime uses the vault \
+             command, so api_key is ignored; remove it, or fix the vault command if api_key is the \
+             right key."
+        }
+        DiagnosticCode::KeyKeyring => {
+            "api_key and api_key_vault_cmd are both unset, so the API key was read from the system \
+             keyring, where wakadoctor --store-key puts it. This needs wakadoctor built with the \
+             keyring feature."
+        }
+        DiagnosticCode::KeyringFailed => {
+            "The system keyring could not be read, for example because it is locked or no keyring \
+             service is running. Unlock it, or set api_key or api_key_vault_cmd instead."
+        }
+        DiagnosticCode::KeyQuoted => {
+            "The api_key value is wrapped in quotes or whitespace, usually from copy-pasting it. \
+             Tools reading the config literally send them as part of the key, and authentication \
+             fails. The format checks that follow are about the key without them. --fix removes \
+             them."
+        }
+        DiagnosticCode::KeyValid => "The API key has the format the server expects.",
+        DiagnosticCode::KeyCrossed => {
+            "The API key looks like it belongs to the other server: Wakatime keys start with \
+             waka_, Hackatime keys do not. Copy the key from the settings page of the server \
+             api_url points at."
+        }
+        DiagnosticCode::KeyInvalid => {
+            "The API key does not have the format the server expects, so it will be rejected. Copy \
+             it again from your server's settings page, without quotes or extra characters."
+        }
+        DiagnosticCode::BoolInvalid => {
+            "A boolean setting such as debug, no_ssl_verify or one of the hide_* settings is not \
+             true or false (or 1 or 0), so it is treated as false and the setting is off. Set it \
+             to true or false."
+        }
+        DiagnosticCode::DebugEnabled => {
+            "debug = true is set, so wakatime writes verbose logs on every heartbeat. The log file \
+             grows quickly and records file paths; remove debug once you are done troubleshooting."
+        }
+        DiagnosticCode::LogFile => {
+            "The log_file setting points to a file wakatime can append its logs to."
+        }
+        DiagnosticCode::LogFileDirMissing => {
+            "The directory of log_file does not exist. wakatime does not create it, so nothing is \
+             logged; create the directory or point log_file elsewhere."
+        }
+        DiagnosticCode::LogFileUnwritable => {
+            "log_file cannot be opened for appending, usually because its directory or the file \
+             itself is not writable. wakatime silently logs nothing, which makes problems hard to \
+             debug."
+        }
+        DiagnosticCode::Timeout => "The timeout setting is a valid number of seconds.",
+        DiagnosticCode::TimeoutZero => {
+            "A timeout of 0 disables the request timeout, so a hanging server can block wakatime \
+             forever. Set a positive number of seconds."
+        }
+        DiagnosticCode::TimeoutInvalid => {
+            "The timeout setting is not a whole number of seconds, so wakatime ignores it. Set a \
+             positive integer."
+        }
+        DiagnosticCode::HeartbeatRateLimit => {
+            "heartbeat_rate_limit_seconds, the minimum time between heartbeats, is in a sensible \
+             range."
+        }
+        DiagnosticCode::HeartbeatRateLimitExtreme => {
+            "heartbeat_rate_limit_seconds is very low or very high. Low values send heartbeats so \
+             often they load the server; high values send so few that coding time goes untracked. \
+             wakatime-cli defaults to 120."
+        }
+        DiagnosticCode::HeartbeatRateLimitInvalid => {
+            "heartbeat_rate_limit_seconds is not a whole number of seconds, so wakatime ignores \
+             it. Write it as a plain number, such as 120."
+        }
+        DiagnosticCode::Hostname => {
+            "The hostname setting, which replaces the machine name reported with heartbeats, is a \
+             valid hostname."
+        }
+        DiagnosticCode::HostnameEmpty => {
+            "The hostname setting is present but blank, so heartbeats are attributed to an empty \
+             machine name on your dashboard. Set a name, or remove the line to use the real one."
+        }
+        DiagnosticCode::HostnameInvalid => {
+            "The hostname setting contains spaces or other characters hostnames cannot have, which \
+             dashboards may show oddly or reject. Use only letters, digits, - and ."
+        }
+        DiagnosticCode::Patterns => {
+            "Every pattern in the exclude or include setting is a valid regex."
+        }
+        DiagnosticCode::PatternInvalid => {
+            "A pattern in the exclude or include setting is not a valid regex, so it matches \
+             nothing. The message gives the line and the parse error."
+        }
+        DiagnosticCode::ExcludeUnknownProject => {
+            "exclude_unknown_project is on, so wakatime drops heartbeats for files it cannot place \
+             in a project, such as files outside any git repository. This is a common reason for \
+             missing activity. Also reported when the value is not true or false."
+        }
+        DiagnosticCode::IncludeOnlyWithProjectFile => {
+            "include_only_with_project_file is on, so wakatime only tracks files in a folder \
+             containing a .wakatime-project file, or below one. Everything else is silently \
+             dropped. Also reported when the value is not true or false."
+        }
+        DiagnosticCode::StatusBarEnabled => {
+            "status_bar_enabled is false, so editor plugins do not show wakatime in their status \
+             bar. Time is still tracked, but this is a common reason for not seeing it in the \
+             editor. Also reported when the value is not true or false."
+        }
+        DiagnosticCode::StatusBarCodingActivity => {
+            "status_bar_coding_activity is false, so the status bar shows the wakatime icon but \
+             not today's coding time. Also reported when the value is not true or false."
+        }
+        DiagnosticCode::Projectmap => {
+            "Every entry of the [projectmap] section, which names projects after the paths they \
+             live in, is valid."
+        }
+        DiagnosticCode::ProjectmapInvalid => {
+            "An entry of [projectmap] is not a valid regex, or its project name uses a {N} \
+             placeholder for a capture group the regex does not have. wakatime skips such entries \
+             silently, so files under that path get the wrong project."
+        }
+        DiagnosticCode::PrivacyInconsistent => {
+            "Some hide_* privacy settings are set but related ones are not, so the information you \
+             meant to hide can still leak, for example through the project folder. Set the related \
+             setting too."
+        }
+        DiagnosticCode::ConfigPermissions => {
+            "The config holds your API key in plain text, so other users of the machine should not \
+             be able to read it. On Unix, make it readable only by you with chmod 600, which --fix \
+             can do for you."
+        }
+        DiagnosticCode::InternalBackoff => {
+            "wakatime records failed heartbeats in .wakatime-internal.cfg and backs off from \
+             sending more. When it is backing off, recent heartbeats have been failing; fix the \
+             problems above and wakatime resumes on its own."
+        }
+        DiagnosticCode::OfflineQueue => {
+            "wakatime queues heartbeats it could not send in its offline database. A large queue \
+             means heartbeats have been failing for a while; they are sent once the config works."
+        }
+        DiagnosticCode::UpdateCheck => {
+            "Whether this is the latest wakadoctor release on crates.io, checked with \
+             --check-update. A failure only means crates.io could not be reached, or that this \
+             build's version could not be compared."
+        }
+        DiagnosticCode::UpdateAvailable => {
+            "A newer wakadoctor is on crates.io, which may have more or better checks. Update with \
+             cargo install wakadoctor."
+        }
+        DiagnosticCode::CliMissing => {
+            "wakatime-cli was not found in ~/.wakatime or on PATH. Most editor plugins download it \
+             automatically; otherwise install it yourself. Pass --skip-cli-check to skip this."
+        }
+        DiagnosticCode::CliVersion => {
+            "The version of wakatime-cli that was found. Old versions lack fixes and settings \
+             newer servers rely on; update it if it is older than --min-cli-version."
+        }
+        DiagnosticCode::Proxy => "Requests go through the configured proxy.",
+        DiagnosticCode::ProxyInvalid => {
+            "The proxy setting is not a valid proxy URL, so requests cannot be sent. Use a URL \
+             such as http://host:port or socks5://host:port."
+        }
+        DiagnosticCode::NoSslVerify => {
+            "no_ssl_verify turns off TLS certificate verification, letting anyone on the network \
+             read your API key. Remove it, and use ssl_certs_file if your server has a private \
+             certificate."
+        }
+        DiagnosticCode::SslCertsFile => {
+            "ssl_certs_file names a bundle of PEM certificates used to verify the server. This \
+             fails if the file cannot be read or contains no certificates."
+        }
+        DiagnosticCode::ClientBuild => {
+            "The HTTP client could not be set up from the proxy and TLS settings, so no requests \
+             can be sent. The message gives the reason."
+        }
+        DiagnosticCode::TlsVersion => {
+            "The newest TLS version the server accepted. Servers should support TLS 1.2 or 1.3; \
+             --min-tls refuses anything older."
+        }
+        DiagnosticCode::TlsOutdated => {
+            "The server only accepts TLS 1.1 or older, which have known weaknesses and are \
+             disabled by many TLS libraries, so wakatime-cli may not be able to connect. Update \
+             the server's TLS configuration."
+        }
+        DiagnosticCode::TlsBelowMinimum => {
+            "The server does not support the TLS version required with --min-tls, so every request \
+             fails. Lower --min-tls, or update the server's TLS configuration."
+        }
+        DiagnosticCode::Offline => {
+            "--offline was passed, so the DNS, TLS, server and heartbeat checks were skipped and \
+             nothing was sent over the network. Every other check still ran."
+        }
+        DiagnosticCode::DryRun => {
+            "With --dry-run, the network checks are not run. Instead, each request they would make \
+             is listed, with the API key redacted, in the order it would be made."
+        }
+        DiagnosticCode::Dns => {
+            "The host of api_url resolves to an address, of the family chosen with --ip-version if \
+             one is. When it does not, check for typos in the host and your network connection."
+        }
+        DiagnosticCode::UserOk => {
+            "The server accepted the API key when asked for the current user."
+        }
+        DiagnosticCode::UserRejected => {
+            "The server rejected the API key, so every heartbeat will be rejected too. If the key \
+             is in a valid format it is probably wrong or revoked; if not, or if it looks like a \
+             token for another service, the wrong value was pasted. Either way, copy the key again \
+             from the settings page of the server api_url points at."
+        }
+        DiagnosticCode::UserUnsupported => {
+            "The server does not implement users/current, so the API key could only be checked by \
+             sending a heartbeat with --send-real-heartbeat."
+        }
+        DiagnosticCode::CaptivePortal => {
+            "The server answered with an HTML page instead of JSON. This is usually the login page \
+             of a captive portal on hotel, airport or other public WiFi intercepting requests; log \
+             in through a browser and run the check again."
+        }
+        DiagnosticCode::RateLimited => {
+            "The server is rate limiting requests, so the check could not finish. This is not a \
+             config problem; try again later."
+        }
+        DiagnosticCode::HeartbeatProbeOnly => {
+            "Only a probe that records nothing was sent, and it could not verify the API key. Pass \
+             --send-real-heartbeat to send a test heartbeat."
+        }
+        DiagnosticCode::HeartbeatEndpoints => {
+            "The custom server has both endpoints heartbeats can be sent to: heartbeats, which \
+             takes one, and heartbeats.bulk, which takes a batch. They are probed with an empty \
+             batch, which records nothing."
+        }
+        DiagnosticCode::HeartbeatBulkMissing => {
+            "The custom server answers 404 or 405 on heartbeats.bulk. wakatime-cli sends every \
+             heartbeat there, so it cannot record anything on this server, even though the single \
+             heartbeats endpoint works. Update the server, or check the API path."
+        }
+        DiagnosticCode::HeartbeatSingleMissing => {
+            "The custom server only implements heartbeats.bulk, not the single heartbeats \
+             endpoint. wakatime-cli only needs heartbeats.bulk, but older plugins or scripts that \
+             post single heartbeats will fail."
+        }
+        DiagnosticCode::HeartbeatEndpointsMissing => {
+            "The custom server answers 404 or 405 on both heartbeats and heartbeats.bulk, so it \
+             cannot record heartbeats at all. The API path is most likely wrong, or the server is \
+             not a wakatime-compatible API."
+        }
+        DiagnosticCode::HeartbeatSkipped => {
+            "The heartbeat check needs a valid URL, host, https, a resolving host and an API key, \
+             so it was skipped after an earlier error. Fix the errors above first."
+        }
+        DiagnosticCode::HeartbeatRetry => {
+            "A heartbeat attempt failed and is retried with exponential backoff. --retries sets \
+             how many times."
+        }
+        DiagnosticCode::HeartbeatOk => "The server accepted the test heartbeat.",
+        DiagnosticCode::HeartbeatUnexpectedStatus => {
+            "The server answered the test heartbeat with a status other than success or rejection, \
+             which usually means api_url points at the wrong endpoint or the server is failing."
+        }
+        DiagnosticCode::HeartbeatConnectTimeout => {
+            "No connection to the server could be established in time, so the network between you \
+             and the server is the problem rather than the server itself. Check firewalls, VPNs \
+             and proxies, or raise --connect-timeout."
+        }
+        DiagnosticCode::HeartbeatTimeout => {
+            "A connection was made but the server did not answer within the timeout. Check that it \
+             is up, or raise --timeout if it is slow."
+        }
+        DiagnosticCode::HeartbeatError => {
+            "The test heartbeat could not be sent at all, for example because the connection or \
+             TLS handshake failed. The message gives the reason."
+        }
+        DiagnosticCode::HeartbeatLatency => {
+            "How long the users/current request, and the test heartbeat if one was sent, took. \
+             Slow servers can make editors lag while wakatime waits."
+        }
+        DiagnosticCode::HeartbeatResponse => {
+            "Whether the answer to the test heartbeat looks like a wakatime API response. When it \
+             does not, api_url may point at a website instead of its API."
+        }
+        DiagnosticCode::ClockSkew => {
+            "Compares your system clock with the Date header of the server. Heartbeats carry \
+             timestamps, so a clock that is far off files your time on the wrong day or gets \
+             heartbeats rejected; sync your clock."
+        }
+        DiagnosticCode::HeartbeatCleanup => {
+            "The test heartbeat is removed again after it was sent, unless --no-cleanup is passed. \
+             When removing it fails, it may show up briefly in your stats."
+        }
+        DiagnosticCode::Deadline => {
+            "The network checks took longer than --deadline allows, so those still running were \
+             aborted and their results are missing. A test heartbeat sent with \
+             --send-real-heartbeat may not have been removed. Raise the deadline, or lower \
+             --timeout and --retries."
+        }
+        DiagnosticCode::Configured => {
+            "Every check passed, so wakatime should work with this config."
+        }
+    }
+}
+
+/// The description of the diagnostic `code`, or `None` if there is no such code.
+pub fn explain(code: &str) -> Option<&'static str> {
+    code.parse().ok().map(explanation)
+}
+
+/// Whether the `--only` or `--skip` `selector` selects `code`: it is the code itself, or the
+/// start of the code up to an underscore, such as `heartbeat` for `heartbeat_ok`.
+pub fn selects(selector: &str, code: &str) -> bool {
+    code.strip_prefix(selector)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('_'))
+}
+
+/// Parse a `--only` or `--skip` selector, rejecting those that select no known code.
+pub fn parse_selector(selector: &str) -> Result<String, String> {
+    let selector = selector.trim();
+    if DiagnosticCode::ALL
+        .iter()
+        .any(|code| selects(selector, code.as_str()))
+    {
+        Ok(selector.to_string())
+    } else {
+        Err(format!("unknown diagnostic code \"{selector}\""))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selectors() {
+        assert!(selects("heartbeat", "heartbeat_ok"));
+        assert!(selects("heartbeat_ok", "heartbeat_ok"));
+        assert!(!selects("heart", "heartbeat_ok"));
+        assert!(!selects("heartbeat_ok", "heartbeat"));
+        assert_eq!(parse_selector("cli_version"), Ok("cli_version".to_string()));
+        assert_eq!(parse_selector("host"), Ok("host".to_string()));
+        assert!(parse_selector("cli_verison").is_err());
+    }
+}
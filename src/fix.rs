@@ -0,0 +1,186 @@
+//! Automatic repairs of the config file.
+
+use std::{fmt::Display, path::Path};
+
+use url::Url;
+
+use crate::config;
+
+/// A mechanical repair of the config file text.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Fix {
+    /// Switch `api_url` from `http://` to `https://`.
+    UseHttps,
+    /// Replace the path of `api_url`.
+    SetApiPath(String),
+    /// Replace `api_url` entirely.
+    SetApiUrl(&'static str),
+    /// Add a `[settings]` header at the top of the file.
+    AddSettingsHeader,
+    /// Make the file readable only by its owner. This changes the file mode, not the text.
+    RestrictPermissions,
+    /// Remove quotes and whitespace around `api_key`.
+    UnquoteApiKey,
+}
+
+impl Display for Fix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UseHttps => f.write_str("Switch api_url to https://"),
+            Self::SetApiPath(path) => write!(f, "Set the api_url path to \"{path}\""),
+            Self::SetApiUrl(url) => write!(f, "Set api_url to {url}"),
+            Self::AddSettingsHeader => f.write_str("Add a [settings] header"),
+            Self::RestrictPermissions => {
+                f.write_str("Make the config readable only by you (chmod 600)")
+            }
+            Self::UnquoteApiKey => f.write_str("Remove the quotes and whitespace around api_key"),
+        }
+    }
+}
+
+impl Fix {
+    /// Apply the fix to the config `text`, returning the new text.
+    ///
+    /// Only the line being fixed changes, so other lines keep their bytes, even if they are not
+    /// UTF-8, and their line endings. Fixes to `api_url` are applied to its current value, so
+    /// several of them compose.
+    pub fn apply(&self, text: &[u8]) -> Vec<u8> {
+        match self {
+            Self::UseHttps => update_setting(text, "api_url", |v| {
+                v.strip_prefix("http://")
+                    .map(|rest| format!("https://{rest}"))
+            }),
+            Self::SetApiPath(path) => update_setting(text, "api_url", |v| {
+                let mut url = Url::parse(v).ok()?;
+                url.set_path(path);
+                Some(url.to_string())
+            }),
+            Self::SetApiUrl(url) => update_setting(text, "api_url", |_| Some(url.to_string())),
+            Self::AddSettingsHeader => {
+                let mut out = b"[settings]".to_vec();
+                out.extend_from_slice(line_ending(text));
+                out.extend_from_slice(text);
+                out
+            }
+            Self::RestrictPermissions => text.to_vec(),
+            Self::UnquoteApiKey => {
+                update_setting(text, "api_key", |v| Some(config::unquote(v).to_string()))
+            }
+        }
+    }
+}
+
+/// The line ending `text` uses: `\r\n` if its first line ends with one, `\n` otherwise.
+fn line_ending(text: &[u8]) -> &'static [u8] {
+    match text.iter().position(|v| *v == b'\n') {
+        Some(i) if i > 0 && text[i - 1] == b'\r' => b"\r\n",
+        _ => b"\n",
+    }
+}
+
+/// Replace the value of `key` in the `[settings]` section of `text` with `update(old value)`.
+///
+/// The text is unchanged if the key is missing or `update` returns `None`.
+fn update_setting(text: &[u8], key: &str, update: impl Fn(&str) -> Option<String>) -> Vec<u8> {
+    let mut section = String::new();
+    let mut out = Vec::with_capacity(text.len());
+    for line in text.split_inclusive(|v| *v == b'\n') {
+        let content = line.strip_suffix(b"\n").unwrap_or(line);
+        let content = content.strip_suffix(b"\r").unwrap_or(content);
+        let ending = &line[content.len()..];
+        // Lines that are not UTF-8 cannot be a section header or the key, so they are kept.
+        let Ok(trimmed) = std::str::from_utf8(content).map(str::trim) else {
+            out.extend_from_slice(line);
+            continue;
+        };
+        if let Some(name) = trimmed.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            section = name.trim().to_string();
+        } else if section == "settings"
+            && let Some((k, v)) = trimmed.split_once('=')
+            && k.trim() == key
+            && let Some(new) = update(v.trim())
+        {
+            out.extend_from_slice(format!("{key} = {new}").as_bytes());
+            out.extend_from_slice(ending);
+            continue;
+        }
+        out.extend_from_slice(line);
+    }
+    out
+}
+
+/// Apply `fixes` to the config file at `path`, first copying it to `<path>.bak`.
+///
+/// The backup keeps the permissions of the original, so it is no more readable than the config.
+pub fn apply_fixes(path: &Path, fixes: &[Fix]) -> std::io::Result<()> {
+    let text = std::fs::read(path)?;
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    std::fs::copy(path, &backup)?;
+
+    let text = fixes.iter().fold(text, |text, fix| fix.apply(&text));
+    std::fs::write(path, text)?;
+    if fixes.contains(&Fix::RestrictPermissions) {
+        restrict_permissions(path)?;
+        restrict_permissions(Path::new(&backup))?;
+    }
+    Ok(())
+}
+
+/// Make the file at `path` readable and writable only by its owner.
+#[cfg(unix)]
+fn restrict_permissions(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+}
+
+/// Make the file at `path` readable and writable only by its owner.
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixes_compose() {
+        let text = b"[settings]\napi_key = x\napi_url = http://hackatime.hackclub.com/api/v1\n";
+        let text = Fix::UseHttps.apply(text);
+        let text = Fix::SetApiPath("/api/hackatime/v1".to_string()).apply(&text);
+        assert_eq!(
+            text,
+            b"[settings]\napi_key = x\napi_url = https://hackatime.hackclub.com/api/hackatime/v1\n"
+        );
+    }
+
+    #[test]
+    fn unquotes_api_key() {
+        assert_eq!(
+            Fix::UnquoteApiKey.apply(b"[settings]\napi_key = \" x \"  \napi_url = \"y\"\n"),
+            b"[settings]\napi_key = x\napi_url = \"y\"\n"
+        );
+    }
+
+    #[test]
+    fn adds_settings_header() {
+        assert_eq!(
+            Fix::AddSettingsHeader.apply(b"api_key = x\n"),
+            b"[settings]\napi_key = x\n"
+        );
+        assert_eq!(
+            Fix::AddSettingsHeader.apply(b"api_key = x\r\n"),
+            b"[settings]\r\napi_key = x\r\n"
+        );
+    }
+
+    #[test]
+    fn keeps_other_lines() {
+        let text = b"[settings]\r\nhostname = caf\xe9\r\napi_url = http://my.server/api/v1\r\nproxy = \t\r\n";
+        assert_eq!(
+            Fix::UseHttps.apply(text),
+            b"[settings]\r\nhostname = caf\xe9\r\napi_url = https://my.server/api/v1\r\nproxy = \t\r\n"
+        );
+    }
+}
@@ -0,0 +1,491 @@
+//! Sending a test heartbeat to the server.
+
+use std::time::{Duration, Instant};
+
+use reqwest::StatusCode;
+use url::Url;
+
+use crate::{
+    checks::CheckOptions,
+    client::{self, IpVersion},
+    diagnostic::{Diagnostic, DiagnosticCode, Report},
+    host::WakaHost,
+    user,
+};
+
+/// Diagnostic codes sending the test heartbeat can report.
+pub const CODES: &[DiagnosticCode] = &[
+    DiagnosticCode::HeartbeatOk,
+    DiagnosticCode::HeartbeatError,
+    DiagnosticCode::HeartbeatResponse,
+    DiagnosticCode::HeartbeatUnexpectedStatus,
+    DiagnosticCode::HeartbeatTimeout,
+    DiagnosticCode::HeartbeatConnectTimeout,
+    DiagnosticCode::HeartbeatRetry,
+    DiagnosticCode::HeartbeatLatency,
+    DiagnosticCode::HeartbeatCleanup,
+    DiagnosticCode::ClockSkew,
+    DiagnosticCode::CaptivePortal,
+    DiagnosticCode::RateLimited,
+];
+
+/// Heartbeat timeout in seconds used when none is configured.
+pub const DEFAULT_TIMEOUT: u64 = 10;
+
+/// Clock skew in seconds above which heartbeats may be rejected.
+const MAX_CLOCK_SKEW: i64 = 60;
+
+/// Heartbeat round-trip time above which the connection is reported as slow.
+const SLOW_HEARTBEAT: Duration = Duration::from_secs(2);
+
+/// Entity of the test heartbeat unless `--heartbeat-entity` is passed.
+pub const DEFAULT_ENTITY: &str = "wakadoctor-test.txt";
+
+/// Language of the test heartbeat unless `--heartbeat-language` is passed.
+pub const DEFAULT_LANGUAGE: &str = "Text";
+
+/// Body of a test heartbeat sent at `time`, with the entity, language and project of `opts`.
+fn heartbeat_body(time: i64, opts: &CheckOptions) -> String {
+    let mut heartbeat = serde_json::json!({
+        "type": "file",
+        "time": time,
+        "entity": opts.heartbeat_entity.as_deref().unwrap_or(DEFAULT_ENTITY),
+        "language": opts.heartbeat_language.as_deref().unwrap_or(DEFAULT_LANGUAGE),
+    });
+    if let Some(project) = &opts.heartbeat_project {
+        heartbeat["project"] = project.as_str().into();
+    }
+    serde_json::json!([heartbeat]).to_string()
+}
+
+/// Build the URL of the API endpoint `path` under `url`.
+///
+/// The segments of `path` are appended to the path of `url`, whether or not it ends in a slash,
+/// and any query or fragment of `url` is dropped.
+pub(crate) fn endpoint(url: &Url, path: &str) -> Url {
+    let mut endpoint = url.clone();
+    endpoint.set_query(None);
+    endpoint.set_fragment(None);
+    if let Ok(mut segments) = endpoint.path_segments_mut() {
+        segments.pop_if_empty().extend(path.split('/'));
+    }
+    endpoint
+}
+
+/// Send a test heartbeat to the API at `url`, pushing the outcome to `report`.
+pub async fn send_heartbeat(
+    url: &Url,
+    api_key: &str,
+    host: WakaHost,
+    client: &reqwest::Client,
+    timeout: Duration,
+    opts: &CheckOptions,
+    report: &mut Report,
+) {
+    let now = time::UtcDateTime::now();
+    let body = heartbeat_body(now.unix_timestamp(), opts);
+
+    let mut attempt = 0;
+    let (result, latency) = loop {
+        attempt += 1;
+        let start = Instant::now();
+        let result = client::send(
+            client
+                .post(endpoint(url, "users/current/heartbeats"))
+                .bearer_auth(api_key)
+                .body(body.clone())
+                .header("Content-Type", "application/json")
+                .timeout(timeout),
+            opts.verbose,
+        )
+        .await;
+        let latency = start.elapsed();
+
+        let reason = match &result {
+            Ok(r) if r.status().is_server_error() => r.status().to_string(),
+            Ok(_) => break (result, latency),
+            Err(e) if e.is_connect() && e.is_timeout() => "connection timeout".to_string(),
+            Err(e) if e.is_timeout() => "timeout".to_string(),
+            Err(e) if e.is_connect() => "connection failed".to_string(),
+            Err(_) => "request failed".to_string(),
+        };
+        if attempt > opts.retries {
+            break (result, latency);
+        }
+        report.push(Diagnostic::warning(
+            DiagnosticCode::HeartbeatRetry,
+            format!("Heartbeat attempt {attempt} failed ({reason}), retrying..."),
+        ));
+        tokio::time::sleep(Duration::from_secs(1 << (attempt - 1).min(5))).await;
+    };
+
+    match result {
+        Ok(r) => {
+            // The clock was already compared with the server by the users/current probe.
+            let status = r.status();
+            let html = is_html(&r);
+            if status.is_success() && html {
+                report.push(captive_portal());
+            } else if status.is_success() {
+                if status == StatusCode::CREATED || status == StatusCode::ACCEPTED {
+                    report.push(Diagnostic::ok(
+                        DiagnosticCode::HeartbeatOk,
+                        format!("Server accepted heartbeat ({status})"),
+                    ));
+                } else {
+                    report.push(Diagnostic::warning(
+                        DiagnosticCode::HeartbeatUnexpectedStatus,
+                        format!("Server returned unexpected status for heartbeat ({status})"),
+                    ));
+                }
+                report_latency("Heartbeat", latency, report);
+                let response = check_response_body(&r.text().await.unwrap_or_default(), report);
+                if !opts.no_cleanup {
+                    delete_heartbeat(
+                        url,
+                        api_key,
+                        client,
+                        response
+                            .as_ref()
+                            .and_then(heartbeat_id)
+                            .map(|id| (now.date(), id)),
+                        timeout,
+                        opts.verbose,
+                        report,
+                    )
+                    .await;
+                }
+            } else if status == StatusCode::TOO_MANY_REQUESTS {
+                report.push(rate_limited(&r));
+            } else {
+                let hint = if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN
+                {
+                    format!(" {}", user::rejection_hint(api_key))
+                } else {
+                    String::new()
+                };
+                report.push(Diagnostic::error(
+                    DiagnosticCode::HeartbeatError,
+                    format!(
+                        "Got error status code ({status}). {host} is NOT configured correctly.{hint}"
+                    ),
+                ));
+            }
+        }
+        Err(e) => report.push(request_failed(&e, url, host, timeout, opts)),
+    };
+}
+
+/// Whether `response` is an HTML page, going by its `Content-Type`.
+///
+/// The wakatime API only ever answers with JSON, so HTML comes from something in between, such as
+/// the login page of a captive portal on hotel or airport WiFi.
+pub(crate) fn is_html(response: &reqwest::Response) -> bool {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| {
+            let v = v.to_ascii_lowercase();
+            v.starts_with("text/html") || v.starts_with("application/xhtml")
+        })
+}
+
+/// Warning for a successful response that is an HTML page rather than an API response.
+pub(crate) fn captive_portal() -> Diagnostic {
+    Diagnostic::warning(
+        DiagnosticCode::CaptivePortal,
+        "Response looks like a captive portal page, not the wakatime API",
+    )
+}
+
+/// Error for a request to `url` that failed with `e` before the server answered, telling
+/// connection timeouts apart from slow responses.
+pub(crate) fn request_failed(
+    e: &reqwest::Error,
+    url: &Url,
+    host: WakaHost,
+    timeout: Duration,
+    opts: &CheckOptions,
+) -> Diagnostic {
+    let over = match opts.ip_version {
+        IpVersion::Auto => String::new(),
+        v => format!(" over {v}"),
+    };
+    if e.is_connect() && e.is_timeout() {
+        let seconds = opts.connect_timeout.unwrap_or(timeout.as_secs());
+        let hint = if opts.ip_version == IpVersion::Auto {
+            " If IPv6 is broken on your network, try --ip-version 4."
+        } else {
+            ""
+        };
+        Diagnostic::error(
+            DiagnosticCode::HeartbeatConnectTimeout,
+            format!(
+                "Could not connect to {}{over} within {seconds}s (connection timeout). {host} is NOT configured correctly.{hint}",
+                url.host_str().unwrap_or_default()
+            ),
+        )
+    } else if e.is_timeout() {
+        Diagnostic::error(
+            DiagnosticCode::HeartbeatTimeout,
+            format!(
+                "Server too slow to respond (no response after {} seconds). {host} is NOT configured correctly.",
+                timeout.as_secs()
+            ),
+        )
+    } else if e.is_connect() {
+        Diagnostic::error(
+            DiagnosticCode::HeartbeatError,
+            format!(
+                "Cannot connect to the server{over} with error \"{e}\". {host} is NOT configured correctly."
+            ),
+        )
+    } else {
+        Diagnostic::error(
+            DiagnosticCode::HeartbeatError,
+            format!(
+                "Cannot reach the server with error \"{e}\". {host} is NOT configured correctly."
+            ),
+        )
+    }
+}
+
+/// Warning for a `429 Too Many Requests` `response`, using its `Retry-After` header if present.
+pub(crate) fn rate_limited(response: &reqwest::Response) -> Diagnostic {
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| retry_after_seconds(v, time::UtcDateTime::now()));
+    let retry = match retry_after {
+        Some(v) => format!(" (retry after {v}s)"),
+        None => String::new(),
+    };
+    Diagnostic::warning(
+        DiagnosticCode::RateLimited,
+        format!("Server is rate limiting us{retry}; this is not a config problem"),
+    )
+}
+
+/// Parse a `Retry-After` header, either a number of seconds or an HTTP date, into seconds from
+/// `now`.
+fn retry_after_seconds(value: &str, now: time::UtcDateTime) -> Option<u64> {
+    if let Ok(v) = value.trim().parse() {
+        return Some(v);
+    }
+    let skew = clock_skew(value, now)?;
+    Some((-skew).max(0).unsigned_abs())
+}
+
+/// Seconds the local clock `now` is ahead of the server time in the HTTP `Date` header `date`.
+fn clock_skew(date: &str, now: time::UtcDateTime) -> Option<i64> {
+    let server =
+        time::OffsetDateTime::parse(date, &time::format_description::well_known::Rfc2822).ok()?;
+    Some(now.unix_timestamp() - server.unix_timestamp())
+}
+
+/// Warn if the local clock is too far from the server time in the HTTP `Date` header `date`.
+pub(crate) fn check_clock_skew(date: &str, now: time::UtcDateTime, report: &mut Report) {
+    let Some(skew) = clock_skew(date, now) else {
+        return;
+    };
+    if skew.abs() > MAX_CLOCK_SKEW {
+        report.push(Diagnostic::warning(
+            DiagnosticCode::ClockSkew,
+            format!(
+                "Your system clock is {} seconds off from the server; heartbeats may be rejected",
+                skew.abs()
+            ),
+        ));
+    } else {
+        report.push(Diagnostic::ok(
+            DiagnosticCode::ClockSkew,
+            "System clock is in sync with the server",
+        ));
+    }
+}
+
+/// Report how long `request`, such as `Heartbeat`, took, warning if it was slow.
+pub(crate) fn report_latency(request: &str, latency: Duration, report: &mut Report) {
+    if latency > SLOW_HEARTBEAT {
+        report.push(
+            Diagnostic::warning(
+                DiagnosticCode::HeartbeatLatency,
+                format!(
+                    "{request} took {:.1}s; your connection to the server is slow",
+                    latency.as_secs_f64()
+                ),
+            )
+            .with_latency(latency),
+        );
+    } else {
+        report.push(
+            Diagnostic::ok(
+                DiagnosticCode::HeartbeatLatency,
+                format!("{request} succeeded in {}ms", latency.as_millis()),
+            )
+            .with_latency(latency),
+        );
+    }
+}
+
+/// Check the heartbeat response body looks like it came from a wakatime API.
+///
+/// Returns the parsed response if it does.
+fn check_response_body(body: &str, report: &mut Report) -> Option<serde_json::Value> {
+    match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(v) if v.get("responses").is_some() || v.get("data").is_some() => {
+            report.push(Diagnostic::ok(
+                DiagnosticCode::HeartbeatResponse,
+                "Heartbeat response looks like a wakatime API response",
+            ));
+            return Some(v);
+        }
+        Ok(_) => {
+            report.push(Diagnostic::warning(
+                DiagnosticCode::HeartbeatResponse,
+                "Heartbeat response has no \"responses\" or \"data\" field; the server may not be a wakatime API",
+            ));
+        }
+        Err(_) => {
+            report.push(Diagnostic::warning(
+                DiagnosticCode::HeartbeatResponse,
+                "Heartbeat response is not JSON; the server may not be a wakatime API",
+            ));
+        }
+    }
+    None
+}
+
+/// Find the id of the created heartbeat in a heartbeat `response`.
+///
+/// Handles both the single (`{"data": {...}}`) and bulk (`{"responses": [[{"data": {...}}, 201]]}`)
+/// response shapes.
+fn heartbeat_id(response: &serde_json::Value) -> Option<&str> {
+    response
+        .pointer("/data/id")
+        .or_else(|| response.pointer("/responses/0/0/data/id"))
+        .and_then(|v| v.as_str())
+}
+
+/// Delete the test heartbeat `created`, given as the date it was created on and its id if known,
+/// logging the request if `verbose`.
+async fn delete_heartbeat(
+    url: &Url,
+    api_key: &str,
+    client: &reqwest::Client,
+    created: Option<(time::Date, &str)>,
+    timeout: Duration,
+    verbose: bool,
+    report: &mut Report,
+) {
+    let Some((date, id)) = created else {
+        report.push(Diagnostic::warning(
+            DiagnosticCode::HeartbeatCleanup,
+            "Could not remove test heartbeat; it may appear briefly in your stats",
+        ));
+        return;
+    };
+
+    let deleted = client::send(
+        client
+            .delete(endpoint(url, "users/current/heartbeats.bulk"))
+            .bearer_auth(api_key)
+            .body(serde_json::json!({ "date": date.to_string(), "ids": [id] }).to_string())
+            .header("Content-Type", "application/json")
+            .timeout(timeout),
+        verbose,
+    )
+    .await
+    .is_ok_and(|r| r.status().is_success());
+
+    if deleted {
+        report.push(Diagnostic::ok(
+            DiagnosticCode::HeartbeatCleanup,
+            "Removed test heartbeat",
+        ));
+    } else {
+        report.push(Diagnostic::warning(
+            DiagnosticCode::HeartbeatCleanup,
+            "Could not remove test heartbeat; it may appear briefly in your stats",
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heartbeat_bodies() {
+        assert_eq!(
+            heartbeat_body(1_700_000_000, &CheckOptions::default()),
+            r#"[{"entity":"wakadoctor-test.txt","language":"Text","time":1700000000,"type":"file"}]"#
+        );
+        let opts = CheckOptions {
+            heartbeat_entity: Some("src/main.rs".to_string()),
+            heartbeat_language: Some("Rust".to_string()),
+            heartbeat_project: Some("my \"project\"".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            heartbeat_body(1_700_000_000, &opts),
+            r#"[{"entity":"src/main.rs","language":"Rust","project":"my \"project\"","time":1700000000,"type":"file"}]"#
+        );
+    }
+
+    #[test]
+    fn endpoints() {
+        let heartbeats =
+            |url: &str| endpoint(&Url::parse(url).unwrap(), "users/current/heartbeats").to_string();
+        assert_eq!(
+            heartbeats("https://api.wakatime.com/api/v1"),
+            "https://api.wakatime.com/api/v1/users/current/heartbeats"
+        );
+        assert_eq!(
+            heartbeats("https://api.wakatime.com/api/v1/"),
+            "https://api.wakatime.com/api/v1/users/current/heartbeats"
+        );
+        assert_eq!(
+            heartbeats("https://hackatime.hackclub.com/api/hackatime/v1"),
+            "https://hackatime.hackclub.com/api/hackatime/v1/users/current/heartbeats"
+        );
+        assert_eq!(
+            heartbeats("https://my.server/api?key=1"),
+            "https://my.server/api/users/current/heartbeats"
+        );
+    }
+
+    #[test]
+    fn heartbeat_id_shapes() {
+        let single = serde_json::json!({ "data": { "id": "abc" } });
+        let bulk = serde_json::json!({ "responses": [[{ "data": { "id": "def" } }, 201]] });
+        assert_eq!(heartbeat_id(&single), Some("abc"));
+        assert_eq!(heartbeat_id(&bulk), Some("def"));
+        assert_eq!(heartbeat_id(&serde_json::json!({ "data": {} })), None);
+    }
+
+    #[test]
+    fn retry_after_values() {
+        let now = time::UtcDateTime::from_unix_timestamp(784111777).unwrap();
+        assert_eq!(retry_after_seconds("120", now), Some(120));
+        assert_eq!(
+            retry_after_seconds("Sun, 06 Nov 1994 08:50:07 GMT", now),
+            Some(30)
+        );
+        assert_eq!(
+            retry_after_seconds("Sun, 06 Nov 1994 08:00:00 GMT", now),
+            Some(0)
+        );
+        assert_eq!(retry_after_seconds("soon", now), None);
+    }
+
+    #[test]
+    fn clock_skew_from_date_header() {
+        let now = time::UtcDateTime::from_unix_timestamp(784111777).unwrap();
+        assert_eq!(clock_skew("Sun, 06 Nov 1994 08:49:37 GMT", now), Some(0));
+        assert_eq!(clock_skew("Sun, 06 Nov 1994 08:47:37 GMT", now), Some(120));
+        assert_eq!(clock_skew("yesterday", now), None);
+    }
+}
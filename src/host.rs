@@ -0,0 +1,40 @@
+//! Known API hosts.
+
+use std::{fmt::Display, str::FromStr};
+
+/// The kind of server the API URL points at.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WakaHost {
+    /// Current Hackatime (`hackatime.hackclub.com`).
+    Hackatime,
+    /// Old Hack Club Wakatime (`waka.hackclub.com`).
+    OldHackatime,
+    /// Official Wakatime (`api.wakatime.com`).
+    Wakatime,
+    /// Anything else.
+    Custom,
+}
+
+impl Display for WakaHost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Hackatime | Self::OldHackatime => f.write_str("Hackatime"),
+            Self::Wakatime | Self::Custom => f.write_str("Wakatime"),
+        }
+    }
+}
+
+impl FromStr for WakaHost {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "hackatime" => Ok(Self::Hackatime),
+            "wakatime" => Ok(Self::Wakatime),
+            "custom" => Ok(Self::Custom),
+            _ => Err(format!(
+                "invalid host \"{s}\", expected hackatime, wakatime or custom"
+            )),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Checks of `.wakatime-internal.cfg`, where wakatime-cli keeps its own state.
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticCode, Report},
+    paths,
+};
+
+/// Name of the wakatime-cli internal state file.
+const INTERNAL_FILE_NAME: &str = ".wakatime-internal.cfg";
+
+/// The internal state file.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+struct InternalConfig {
+    /// The `[internal]` section.
+    internal: InternalSection,
+}
+
+/// The `[internal]` section of the internal state file.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+struct InternalSection {
+    /// When wakatime-cli started backing off.
+    backoff_at: String,
+    /// Number of times wakatime-cli has backed off.
+    backoff_retries: String,
+    /// Older name of `backoff_retries`.
+    backoff_count: String,
+}
+
+/// Number of backoffs and since when, if wakatime-cli is backing off according to `text`.
+fn backoff(text: &str) -> Result<Option<(u64, String)>, String> {
+    let config = serde_ini::from_str::<InternalConfig>(text).map_err(|e| e.to_string())?;
+    let section = config.internal;
+    let retries = [&section.backoff_retries, &section.backoff_count]
+        .into_iter()
+        .find(|v| !v.trim().is_empty())
+        .map(|v| v.trim().parse::<u64>())
+        .transpose()
+        .map_err(|e| format!("invalid backoff count ({e})"))?
+        .unwrap_or(0);
+    if retries == 0 {
+        return Ok(None);
+    }
+    let since = match section.backoff_at.trim() {
+        "" => "an unknown time".to_string(),
+        v => v.to_string(),
+    };
+    Ok(Some((retries, since)))
+}
+
+/// Warn if wakatime-cli's internal state file for the config at `config_path` shows it is backing off.
+pub fn check_internal(config_path: &Path, report: &mut Report) {
+    let Some((path, text)) = paths::state_dirs(config_path).into_iter().find_map(|dir| {
+        let path = dir.join(INTERNAL_FILE_NAME);
+        let text = std::fs::read_to_string(&path).ok()?;
+        Some((path, text))
+    }) else {
+        return;
+    };
+
+    match backoff(&text) {
+        Ok(None) => report.push(Diagnostic::ok(
+            DiagnosticCode::InternalBackoff,
+            "wakatime is not backing off from failed heartbeats",
+        )),
+        Ok(Some((retries, since))) => report.push(Diagnostic::warning(
+            DiagnosticCode::InternalBackoff,
+            format!(
+                "wakatime has backed off {retries} times since {since}; something has been failing"
+            ),
+        )),
+        Err(e) => report.push(Diagnostic::warning(
+            DiagnosticCode::InternalBackoff,
+            format!("Cannot parse {} with error \"{e}\"", path.display()),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_backoff() {
+        assert_eq!(backoff("[internal]\n"), Ok(None));
+        assert_eq!(backoff("[internal]\nbackoff_retries = 0\n"), Ok(None));
+        assert_eq!(
+            backoff("[internal]\nbackoff_at = 2025-01-02T03:04:05Z\nbackoff_retries = 7\n"),
+            Ok(Some((7, "2025-01-02T03:04:05Z".to_string())))
+        );
+        assert_eq!(
+            backoff("[internal]\nbackoff_count = 2\n"),
+            Ok(Some((2, "an unknown time".to_string())))
+        );
+        assert!(backoff("[internal]\nbackoff_retries = lots\n").is_err());
+    }
+}
@@ -0,0 +1,36 @@
+//! Reading and storing the API key in the system keyring, with the `keyring` feature.
+
+/// Service the API key is stored under in the keyring.
+pub const KEYRING_SERVICE: &str = "wakatime";
+
+/// User the API key is stored under in the keyring.
+pub const KEYRING_USER: &str = "api_key";
+
+/// The keyring entry holding the API key.
+fn entry() -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)
+        .map_err(|e| format!("Cannot open system keyring with error \"{e}\""))
+}
+
+/// Read the API key from the keyring, or `None` if none is stored.
+///
+/// On failure, returns a message describing what went wrong.
+pub fn read_key() -> Result<Option<String>, String> {
+    match entry()?.get_password() {
+        Ok(v) if v.trim().is_empty() => Ok(None),
+        Ok(v) => Ok(Some(v.trim().to_string())),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!(
+            "Cannot read API key from system keyring with error \"{e}\""
+        )),
+    }
+}
+
+/// Store `key` as the API key in the keyring, replacing any stored before.
+///
+/// On failure, returns a message describing what went wrong.
+pub fn store_key(key: &str) -> Result<(), String> {
+    entry()?
+        .set_password(key)
+        .map_err(|e| format!("Cannot store API key in system keyring with error \"{e}\""))
+}
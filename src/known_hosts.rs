@@ -0,0 +1,46 @@
+//! The user-maintained list of trusted custom hosts.
+
+use std::{io::Write, path::Path};
+
+/// Parse a known hosts file: one hostname per line, with blank lines and `#` comments ignored.
+fn parse(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|v| !v.is_empty() && !v.starts_with('#'))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Read the known hosts file at `path`. A missing file has no hosts.
+pub fn load(path: &Path) -> std::io::Result<Vec<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(v) => Ok(parse(&v)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Append `host` to the known hosts file at `path`, creating it if needed.
+pub fn remember(path: &Path, host: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{host}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hosts() {
+        assert_eq!(
+            parse("# my servers\nwaka.example.com\n\n  Wakapi.Local  \n"),
+            ["waka.example.com", "wakapi.local"]
+        );
+    }
+}
@@ -0,0 +1,37 @@
+//! Wakatime config tester.
+#![warn(missing_docs, clippy::missing_docs_in_private_items)]
+
+pub mod api_url;
+pub mod bulk;
+pub mod checks;
+pub mod client;
+pub mod config;
+pub mod diagnostic;
+pub mod dns;
+pub mod dry_run;
+pub mod dump;
+pub mod explain;
+pub mod fix;
+pub mod heartbeat;
+pub mod host;
+pub mod internal;
+#[cfg(feature = "keyring")]
+pub mod keychain;
+pub mod known_hosts;
+pub mod output;
+pub mod paths;
+pub mod permissions;
+pub mod projectmap;
+pub mod queue;
+pub mod redact;
+pub mod settings;
+pub mod tls;
+pub mod update;
+pub mod user;
+pub mod vault;
+pub mod wakapi;
+pub mod wakatime_cli;
+
+pub use checks::{CheckOptions, run_checks};
+pub use config::{WakaConfig, WakaSettings};
+pub use diagnostic::{Diagnostic, DiagnosticCode, Level, Report, Summary};
@@ -1,278 +1,785 @@
 //! Wakatime config tester.
 #![warn(missing_docs, clippy::missing_docs_in_private_items)]
 
-use std::fmt::Display;
+use std::{
+    io::{BufRead, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Duration,
+};
 
-use clap::Parser;
-use serde::Deserialize;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use wakadoctor::{
+    CheckOptions, Diagnostic, DiagnosticCode, Report, Summary,
+    client::{IpVersion, TlsVersion},
+    config, dump, explain, fix,
+    host::WakaHost,
+    output::{self, RenderOptions},
+    paths, redact,
+    update::Version,
+};
+
+/// Output format.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+enum Format {
+    /// Human readable lines.
+    Human,
+    /// A JSON document.
+    Json,
+    /// A Test Anything Protocol stream.
+    Tap,
+    /// A JUnit XML test suite.
+    Junit,
+    /// A SARIF 2.1.0 log, for code scanning dashboards.
+    Sarif,
+}
+
+/// When to color output.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+enum ColorChoice {
+    /// Color when stdout is a terminal and `NO_COLOR` is not set.
+    Auto,
+    /// Always color.
+    Always,
+    /// Never color.
+    Never,
+}
+
+/// How long the config must be left alone after a change before `--watch` re-runs the checks.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Help text describing the network modes and exit codes of a check.
+const CHECK_HELP: &str = "Network modes:
+  --offline              No network access at all
+  --dry-run              No network access, listing the requests that would be made
+  (default)              Probe the API with GET users/current, which records nothing
+  --send-real-heartbeat  Also send a test heartbeat, removed again unless --no-cleanup
+
+Exit codes:
+  0  All checks passed
+  1  At least one check failed
+  2  Only warnings were produced (1 with --strict)";
 
 /// Wakatime configuration tester. Tests for presense of the wakatime CLI, validates API keys, and more.
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
-struct Args {
-    /// Location of the wakatime config file.
-    #[arg(short, long, default_value = "~/.wakatime.cfg")]
-    config_location: String,
+#[command(
+    version,
+    about,
+    long_about = None,
+    after_help = CHECK_HELP,
+    args_conflicts_with_subcommands = true
+)]
+struct Cli {
+    /// What to do. [default: check]
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Arguments of `check` when no subcommand is given.
+    #[command(flatten)]
+    check: CheckArgs,
+}
+
+/// Subcommands.
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check the wakatime config.
+    #[command(after_help = CHECK_HELP)]
+    Check(Box<CheckArgs>),
+    /// Print shell completions.
+    Completions {
+        /// Shell to print completions for.
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+    /// Print the man page.
+    Man,
+    /// Describe what a diagnostic code means and how to fix it.
+    Explain {
+        /// Code shown in brackets in each diagnostic, such as `api_url_invalid`.
+        code: String,
+    },
+}
+
+/// Arguments of the `check` subcommand.
+#[derive(clap::Args, Debug)]
+struct CheckArgs {
+    /// Location of the wakatime config file, or `-` to read it from stdin. Repeat to check
+    /// several configs in turn. [default: $WAKATIME_HOME/.wakatime.cfg or ~/.wakatime.cfg]
+    #[arg(short, long)]
+    config_location: Vec<String>,
     /// Assume you AREN'T trying to use Hackatime.
     #[arg(short = 'w', long = "no-warn-default-waka", default_value_t = false)]
     no_warn_default_waka: bool,
     /// Assume you ARE trying to use a custom server.
     #[arg(short = 'u', long = "custom-server", default_value_t = false)]
     custom_server: bool,
-    /// Do not attempt to send a heartbeat to test the server.
+    /// Treat the API host as this kind of server, applying its path and key rules: hackatime,
+    /// wakatime or custom. Useful when a LAN address or tunnel hides which server it is.
+    #[arg(long = "assume-host", value_name = "HOST")]
+    assume_host: Option<WakaHost>,
+    /// Do not check the API path, for reverse proxies that rewrite it. Host and scheme are still
+    /// checked.
+    #[arg(long = "no-path-check", default_value_t = false)]
+    no_path_check: bool,
+    /// Do not use the network at all.
     #[arg(short = 'o', long = "offline", default_value_t = false)]
     offline: bool,
+    /// List the network requests the checks would make, with the API key redacted, instead of
+    /// making them.
+    #[arg(long = "dry-run", default_value_t = false, conflicts_with = "offline")]
+    dry_run: bool,
+    /// Send a real test heartbeat instead of only probing the API.
+    #[arg(
+        long = "send-real-heartbeat",
+        default_value_t = false,
+        conflicts_with = "offline"
+    )]
+    send_real_heartbeat: bool,
+    /// File the test heartbeat is for. [default: wakadoctor-test.txt]
+    #[arg(
+        long = "heartbeat-entity",
+        value_name = "ENTITY",
+        requires = "send_real_heartbeat"
+    )]
+    heartbeat_entity: Option<String>,
+    /// Language of the test heartbeat. [default: Text]
+    #[arg(
+        long = "heartbeat-language",
+        value_name = "LANGUAGE",
+        requires = "send_real_heartbeat"
+    )]
+    heartbeat_language: Option<String>,
+    /// Project of the test heartbeat. [default: none]
+    #[arg(
+        long = "heartbeat-project",
+        value_name = "PROJECT",
+        requires = "send_real_heartbeat"
+    )]
+    heartbeat_project: Option<String>,
+    /// Print more detail, running extra checks where needed and logging HTTP requests and
+    /// responses to stderr.
+    #[arg(short = 'v', long = "verbose", default_value_t = false)]
+    verbose: bool,
+    /// Exit with a failure code when there are warnings.
+    #[arg(short = 's', long = "strict", default_value_t = false)]
+    strict: bool,
+    /// Do not remove the test heartbeat after sending it.
+    #[arg(long = "no-cleanup", default_value_t = false)]
+    no_cleanup: bool,
+    /// Heartbeat timeout in seconds, overriding `timeout` in the config. [default: 10]
+    #[arg(short = 't', long = "timeout", value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    timeout: Option<u64>,
+    /// Timeout in seconds for establishing a connection, separate from --timeout. [default: none]
+    #[arg(long = "connect-timeout", value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    connect_timeout: Option<u64>,
+    /// Abort the network checks still running after this many seconds in total, including
+    /// retries. [default: none]
+    #[arg(long = "deadline", value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    deadline: Option<u64>,
+    /// IP version to connect with: 4, 6 or auto. Forcing 4 helps diagnose broken IPv6 networks.
+    #[arg(long = "ip-version", value_name = "VERSION", default_value = "auto")]
+    ip_version: IpVersion,
+    /// Oldest TLS version to connect with: 1.2 or 1.3. [default: whatever the TLS library allows]
+    #[arg(long = "min-tls", value_name = "VERSION")]
+    min_tls: Option<TlsVersion>,
+    /// Number of times to retry a failed heartbeat, with exponential backoff.
+    #[arg(short = 'r', long = "retries", value_name = "N", default_value_t = 2)]
+    retries: u32,
+    /// Proxy URL, overriding `proxy` in the config.
+    #[arg(short = 'p', long = "proxy", value_name = "URL")]
+    proxy: Option<String>,
+    /// Profile to apply over `[settings]`, from a `[settings.<PROFILE>]` section or
+    /// ~/.config/wakadoctor/profiles/<PROFILE>.cfg.
+    #[arg(long = "profile", value_name = "PROFILE")]
+    profile: Option<String>,
+    /// Expand `${VAR}` and `$VAR` in config values, which wakatime-cli itself does not do.
+    #[arg(long = "expand-env", default_value_t = false)]
+    expand_env: bool,
+    /// Also check crates.io for a newer wakadoctor. The answer is cached for an hour.
+    #[arg(long = "check-update", default_value_t = false)]
+    check_update: bool,
+    /// Do not look for the wakatime CLI.
+    #[arg(long = "skip-cli-check", default_value_t = false)]
+    skip_cli_check: bool,
+    /// Warn when the wakatime CLI is older than this version. [default: 1.73.0]
+    #[arg(long = "min-cli-version", value_name = "VERSION")]
+    min_cli_version: Option<Version>,
+    /// Do not warn about inconsistent `hide_*` privacy settings.
+    #[arg(long = "no-privacy-check", default_value_t = false)]
+    no_privacy_check: bool,
+    /// API key to test, overriding `api_key` and `api_key_vault_cmd` in the config.
+    #[arg(long = "api-key", value_name = "KEY", value_parser = clap::builder::NonEmptyStringValueParser::new())]
+    api_key: Option<String>,
+    /// API URL to test, overriding `api_url` in the config.
+    #[arg(long = "api-url", value_name = "URL", value_parser = clap::builder::NonEmptyStringValueParser::new())]
+    api_url: Option<String>,
+    /// File listing trusted custom hosts, one per line. [default: ~/.config/wakadoctor/known_hosts]
+    #[arg(long = "known-hosts", value_name = "FILE")]
+    known_hosts: Option<PathBuf>,
+    /// Add the custom host being tested to the known hosts file.
+    #[arg(long = "remember-host", default_value_t = false)]
+    remember_host: bool,
+    /// Do not check `.wakatime-internal.cfg` for signs of failing heartbeats.
+    #[arg(long = "no-internal-check", default_value_t = false)]
+    no_internal_check: bool,
+    /// User-Agent of requests. [default: one mimicking wakatime-cli]
+    #[arg(long = "user-agent", value_name = "USER_AGENT")]
+    user_agent: Option<String>,
+    /// Re-run the checks every time the config file changes.
+    #[arg(long = "watch", default_value_t = false, conflicts_with = "fix")]
+    watch: bool,
+    /// Offer to repair problems that can be fixed automatically, rewriting the config file.
+    #[arg(long = "fix", default_value_t = false, conflicts_with = "api_url")]
+    fix: bool,
+    /// Apply every fix without prompting (with --fix).
+    #[arg(short = 'y', long = "yes", default_value_t = false, requires = "fix")]
+    yes: bool,
+    /// Print the JSON Schema of --format json output instead of checking.
+    #[arg(long = "json-schema", default_value_t = false)]
+    json_schema: bool,
+    /// Print the effective settings after every override instead of checking them.
+    #[arg(
+        long = "dump-config",
+        default_value_t = false,
+        conflicts_with_all = ["watch", "fix"]
+    )]
+    dump_config: bool,
+    /// Do not redact the API key and proxy password (with --dump-config).
+    #[arg(
+        long = "show-secrets",
+        default_value_t = false,
+        requires = "dump_config"
+    )]
+    show_secrets: bool,
+    /// Store the API key in the system keyring instead of checking. The key is taken from
+    /// --api-key, or read from stdin.
+    #[cfg(feature = "keyring")]
+    #[arg(
+        long = "store-key",
+        default_value_t = false,
+        conflicts_with_all = ["watch", "fix", "dump_config"]
+    )]
+    store_key: bool,
+    /// Only report these diagnostic codes, separated by commas, and skip network probes and the
+    /// CLI check that report none of them. A code prefix up to an underscore selects every code
+    /// starting with it, so `heartbeat` selects `heartbeat_ok`.
+    #[arg(long = "only", value_name = "CODES", value_delimiter = ',', value_parser = explain::parse_selector)]
+    only: Vec<String>,
+    /// Do not report these diagnostic codes, separated by commas, selected as with --only, and
+    /// skip probes that only report them.
+    #[arg(long = "skip", value_name = "CODES", value_delimiter = ',', value_parser = explain::parse_selector)]
+    skip: Vec<String>,
+    /// Only print warnings, errors and the summary, and nothing if everything passed.
+    #[arg(short = 'q', long = "quiet", default_value_t = false)]
+    quiet: bool,
+    /// Prefix lines with [OK], [INFO], [WARN] and [FAIL] instead of emoji. [default: only when
+    /// the terminal is not UTF-8]
+    #[arg(long = "no-emoji", default_value_t = false)]
+    no_emoji: bool,
+    /// When to color output.
+    #[arg(long = "color", value_enum, value_name = "WHEN", default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+    /// End human readable output with a table of each check and its status. [default: only
+    /// when stdout is a terminal]
+    #[arg(long = "table", default_value_t = false)]
+    table: bool,
+    /// Output format.
+    #[arg(short = 'f', long = "format", value_enum, default_value_t = Format::Human)]
+    format: Format,
+    /// Write the report to this file instead of stdout, printing only the summary. Missing
+    /// parent directories are created.
+    #[arg(long = "output", value_name = "FILE")]
+    output: Option<PathBuf>,
 }
 
-#[derive(Deserialize, Default)]
-#[serde(default)]
-#[allow(clippy::missing_docs_in_private_items)]
-struct WakaSettings {
-    debug: bool,
-    api_key: String,
-    api_key_vault_cmd: String,
-    api_url: String,
-    hide_file_names: bool,
-    hide_project_names: bool,
-    hide_branch_names: bool,
-    hide_dependencies: bool,
-    hide_project_folder: bool,
-}
+/// Print `report` on the config at `location` in the format of `args` and return the exit code.
+///
+/// With `--output`, the report is written to that file instead and only the summary is printed.
+fn print_report(
+    report: &Report,
+    args: &CheckArgs,
+    location: &str,
+    opts: &RenderOptions,
+) -> ExitCode {
+    let code = ExitCode::from(Summary::of(report).exit_code(args.strict));
+    let config = (location != "-")
+        .then(|| paths::resolve_config_file(location).ok())
+        .flatten();
+    let Some(path) = &args.output else {
+        match args.format {
+            Format::Human => print!("{}", output::render_human(report, opts)),
+            Format::Json => println!("{}", output::render_json(report, opts)),
+            Format::Tap => print!("{}", output::render_tap(report, opts)),
+            Format::Junit => print!("{}", output::render_junit(report, opts)),
+            Format::Sarif => println!("{}", output::render_sarif(report, opts, config.as_deref())),
+        }
+        return code;
+    };
 
-#[derive(Deserialize)]
-#[allow(clippy::missing_docs_in_private_items)]
-struct WakaConfig {
-    settings: WakaSettings,
-}
+    // Escapes would only clutter a file.
+    let file_opts = RenderOptions {
+        color: false,
+        ..*opts
+    };
+    let rendered = match args.format {
+        Format::Human => output::render_human(report, &file_opts),
+        Format::Json => format!("{}\n", output::render_json(report, &file_opts)),
+        Format::Tap => output::render_tap(report, &file_opts),
+        Format::Junit => output::render_junit(report, &file_opts),
+        Format::Sarif => format!(
+            "{}\n",
+            output::render_sarif(report, &file_opts, config.as_deref())
+        ),
+    };
+    let written = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => std::fs::create_dir_all(dir),
+        _ => Ok(()),
+    }
+    .and_then(|()| std::fs::write(path, rendered));
+    if let Err(e) = written {
+        eprintln!(
+            "Cannot write report to {} with error \"{e}\"",
+            path.display()
+        );
+        return ExitCode::FAILURE;
+    }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-#[allow(clippy::missing_docs_in_private_items)]
-enum WakaHost {
-    Hackatime,
-    OldHackatime,
-    Wakatime,
-    Custom,
+    let summary = Summary::of(report);
+    if !opts.quiet || summary.level().is_problem() {
+        println!("{summary}");
+        println!("Report written to {}", path.display());
+    }
+    code
 }
 
-impl Display for WakaHost {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Hackatime | Self::OldHackatime => f.write_str("Hackatime"),
-            Self::Wakatime | Self::Custom => f.write_str("Wakatime"),
-        }
+/// Ask the user `prompt` on stderr, returning whether they answered yes.
+fn confirm(prompt: &str) -> bool {
+    eprint!("{prompt}");
+    let _ = std::io::stderr().flush();
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
     }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
 }
 
-#[tokio::main]
-async fn main() {
-    let mut args = Args::parse();
-    args.config_location = args
-        .config_location
-        .replace("~", &std::env::home_dir().unwrap().to_string_lossy());
-
-    println!("Wakadoctor - Test your wakatime configuration");
-    println!("Version {}", env!("CARGO_PKG_VERSION"));
-    println!();
-
-    let config: WakaConfig =
-        match serde_ini::from_str(match &std::fs::read_to_string(args.config_location) {
-            Ok(v) => {
-                println!("✅ - Successfully read Wakatime config");
-                v
-            }
-            Err(e) => {
-                println!("❌ - Cannot read Wakatime config with error \"{e}\"");
-                return;
-            }
-        }) {
-            Ok(v) => {
-                println!("✅ - Successfully parsed Wakatime config");
-                v
-            }
-            Err(e) => {
-                println!("❌ - Cannot parse Wakatime config with error \"{e}\"");
-                return;
-            }
-        };
-
-    let url = if config.settings.api_url.is_empty() {
-        println!(
-            "⚠️ - Wakatime API URL is not specified - assuming default (https://api.wakatime.com/api/v1)"
-        );
-        url::Url::parse("https://api.wakatime.com/api/v1").unwrap()
-    } else {
-        match url::Url::parse(&config.settings.api_url) {
-            Ok(v) => {
-                println!("✅ - Wakatime API URL is valid URL");
-                v
-            }
-            Err(e) => {
-                println!(
-                    "❌ - Wakatime API URL is not valid URL (failed parsing with error {e})"
-                );
-                return;
-            }
+/// Offer to apply the fixes attached to `report` to the config file at `location`, printing
+/// diagnostics with ASCII labels if `ascii`.
+///
+/// Everything is printed to stderr so it does not mix with the report.
+fn offer_fixes(report: &Report, location: &str, yes: bool, ascii: bool) {
+    let fixable = report
+        .iter()
+        .filter_map(|d| Some((d, d.fix.clone()?)))
+        .collect::<Vec<_>>();
+    if fixable.is_empty() {
+        eprintln!("\nNothing to fix automatically.");
+        return;
+    }
+    if location == "-" {
+        eprintln!("\nCannot fix a config read from stdin; pass its path with --config-location.");
+        return;
+    }
+    let path = match paths::resolve_config_file(location) {
+        Ok(v) => v.to_string_lossy().into_owned(),
+        Err(e) => {
+            eprintln!("\nCannot find config file: {e}");
+            return;
         }
     };
 
-    let host = match url.host_str().unwrap_or_else(|| {
-        println!("❌ - Wakatime API URL has null host");
-        ""
-    }) {
-        "hackatime.hackclub.com" => {
-            println!("✅ - Wakatime API host is Hackatime host");
-            WakaHost::Hackatime
-        }
-        "waka.hackclub.com" => {
-            println!("⚠️ - Wakatime API host is old Hackclub Wakatime host");
-            WakaHost::OldHackatime
+    let mut fixes = Vec::new();
+    for (diagnostic, fix) in fixable {
+        eprintln!("\n{}\n  Fix: {fix}", diagnostic.line(ascii));
+        if yes || confirm("Fix this? [y/N] ") {
+            fixes.push(fix);
         }
-        "api.wakatime.com" => {
-            if args.no_warn_default_waka {
-                println!("✅ - Wakatime API host is default Wakatime host");
-            } else {
-                println!(
-                    "⚠️ - Wakatime API host is default Wakatime host (psst- disable this warning with --no-warn-default-waka)"
-                );
+    }
+    if fixes.is_empty() {
+        eprintln!("\nNo changes made.");
+        return;
+    }
+
+    match fix::apply_fixes(Path::new(&path), &fixes) {
+        Ok(()) => {
+            eprintln!("\nUpdated {path} (backup saved to {path}.bak):");
+            for fix in &fixes {
+                eprintln!("  - {fix}");
             }
-            WakaHost::Wakatime
+            eprintln!("Run wakadoctor again to check the result.");
         }
-        "" => {
-            return;
+        Err(e) => eprintln!("\nCannot write fixes to {path} with error \"{e}\""),
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match cli.command {
+        None => check(cli.check).await,
+        Some(Command::Check(args)) => check(*args).await,
+        Some(Command::Completions { shell }) => {
+            clap_complete::generate(
+                shell,
+                &mut Cli::command(),
+                env!("CARGO_BIN_NAME"),
+                &mut std::io::stdout(),
+            );
+            ExitCode::SUCCESS
         }
-        _ => {
-            if args.custom_server {
-                println!("✅ - Wakatime API host is custom server host");
-            } else {
-                println!(
-                    "⚠️ - Wakatime API host is custom server host or invalid host (psst- disable this warning with --custom-server)"
-                );
+        Some(Command::Man) => {
+            match clap_mangen::Man::new(Cli::command()).render(&mut std::io::stdout()) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("Cannot write man page with error \"{e}\"");
+                    ExitCode::FAILURE
+                }
             }
-            WakaHost::Custom
         }
+        Some(Command::Explain { code }) => match explain::explain(&code) {
+            Some(v) => {
+                println!("[{code}]\n\n{v}");
+                ExitCode::SUCCESS
+            }
+            None => {
+                eprintln!("Unknown diagnostic code \"{code}\"");
+                ExitCode::FAILURE
+            }
+        },
+    }
+}
+
+/// Run the checks described by `args`, print the report and return the exit code.
+async fn check(args: CheckArgs) -> ExitCode {
+    let opts = CheckOptions {
+        no_warn_default_waka: args.no_warn_default_waka,
+        custom_server: args.custom_server,
+        assume_host: args.assume_host,
+        no_path_check: args.no_path_check,
+        offline: args.offline,
+        dry_run: args.dry_run,
+        send_real_heartbeat: args.send_real_heartbeat,
+        heartbeat_entity: args.heartbeat_entity.clone(),
+        heartbeat_language: args.heartbeat_language.clone(),
+        heartbeat_project: args.heartbeat_project.clone(),
+        verbose: args.verbose,
+        no_cleanup: args.no_cleanup,
+        timeout: args.timeout,
+        connect_timeout: args.connect_timeout,
+        deadline: args.deadline,
+        ip_version: args.ip_version,
+        min_tls: args.min_tls,
+        retries: args.retries,
+        proxy: args.proxy.clone(),
+        skip_cli_check: args.skip_cli_check,
+        min_cli_version: args.min_cli_version.clone(),
+        no_privacy_check: args.no_privacy_check,
+        api_key: args.api_key.clone(),
+        api_url: args.api_url.clone(),
+        known_hosts_file: args
+            .known_hosts
+            .clone()
+            .or_else(paths::default_known_hosts_file),
+        remember_host: args.remember_host,
+        user_agent: args.user_agent.clone(),
+        no_internal_check: args.no_internal_check,
+        expand_env: args.expand_env,
+        check_update: args.check_update,
+        only: args.only.clone(),
+        skip: args.skip.clone(),
+    };
+    let render = RenderOptions {
+        quiet: args.quiet,
+        color: match args.color {
+            ColorChoice::Auto => output::auto_color(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        },
+        ascii: args.no_emoji || output::auto_ascii(),
+        table: args.table
+            || (!args.quiet && args.output.is_none() && std::io::stdout().is_terminal()),
     };
 
-    match host {
-        WakaHost::Hackatime => {
-            if url.path() != "/api/hackatime/v1" {
-                println!(
-                    "❌ - Hackatime API path should be \"/api/hackatime/v1\", not \"{}\"",
-                    url.path()
-                );
-                return;
-            } else {
-                println!("✅ - Hackatime API path is correct.");
+    #[cfg(feature = "keyring")]
+    if args.store_key {
+        return store_key(args.api_key.as_deref());
+    }
+    if args.json_schema {
+        println!("{}", output::json_schema());
+        return ExitCode::SUCCESS;
+    }
+
+    let mut prelude = Report::new();
+    let config_location = match args.config_location.as_slice() {
+        [] => {
+            let (location, wakatime_home) = paths::default_config_location();
+            if let Some(wakatime_home) = wakatime_home {
+                prelude.push(Diagnostic::info(
+                    DiagnosticCode::WakatimeHome,
+                    format!("Using WAKATIME_HOME={wakatime_home} to locate config"),
+                ));
             }
+            location
         }
-        WakaHost::OldHackatime => {}
-        WakaHost::Wakatime => {
-            if url.path() != "/api/v1" {
-                println!(
-                    "❌ - Wakatime API path should be \"/api/v1\", not \"{}\"",
-                    url.path()
-                );
-                return;
-            } else {
-                println!("✅ - Wakatime API path is correct.");
+        [location] => location.clone(),
+        locations => return run_many(&args, locations, &opts, &render).await,
+    };
+    if args.dump_config {
+        return dump_config(&args, &config_location, prelude, &opts, &render).await;
+    }
+    if args.watch {
+        return watch(&args, &config_location, &prelude, &opts, &render).await;
+    }
+
+    let (report, code) = run_once(&args, &config_location, &prelude, &opts, &render).await;
+    if args.fix {
+        offer_fixes(&report, &config_location, args.yes, render.ascii);
+    }
+    code
+}
+
+/// Store `api_key`, or a key read from stdin, in the system keyring.
+#[cfg(feature = "keyring")]
+fn store_key(api_key: Option<&str>) -> ExitCode {
+    let key = match api_key {
+        Some(v) => v.to_string(),
+        None => {
+            eprint!("API key: ");
+            let _ = std::io::stderr().flush();
+            let mut key = String::new();
+            if let Err(e) = std::io::stdin().lock().read_line(&mut key) {
+                eprintln!("Cannot read API key with error \"{e}\"");
+                return ExitCode::FAILURE;
             }
+            key.trim().to_string()
         }
-        WakaHost::Custom => {}
+    };
+    if key.is_empty() {
+        eprintln!("No API key given; nothing stored.");
+        return ExitCode::FAILURE;
     }
-
-    if url.scheme() != "https" {
-        if url.scheme() == "http" {
-            println!(
-                "❌ - Wakatime API URL is unsecured HTTP"
-            );
-        } else {
+    match wakadoctor::keychain::store_key(&key) {
+        Ok(()) => {
             println!(
-                "❌ - Wakatime API URL has unknown scheme \"{}\"",
-                url.scheme()
+                "Stored API key in system keyring (service \"{}\")",
+                wakadoctor::keychain::KEYRING_SERVICE
             );
+            ExitCode::SUCCESS
         }
-        return;
-    } else {
-        println!(
-            "✅ - Wakatime API URL is HTTPS"
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Load the config at `location` and print its effective settings, or the report after
+/// `prelude` if it cannot be loaded.
+async fn dump_config(
+    args: &CheckArgs,
+    location: &str,
+    mut report: Report,
+    opts: &CheckOptions,
+    render: &RenderOptions,
+) -> ExitCode {
+    let Some(config) = config::load_config(location, args.profile.as_deref(), &mut report) else {
+        redact::scrub_report(&mut report, &[opts.api_key.as_deref()]);
+        return print_report(&report, args, location, render);
+    };
+    let settings = dump::effective_settings(&config, opts, args.show_secrets).await;
+    match args.format {
+        Format::Json => println!("{}", dump::render_json(&settings)),
+        // Settings are not test results, so test report formats get the table.
+        _ => print!("{}", dump::render_human(&settings)),
+    }
+    ExitCode::SUCCESS
+}
+
+/// Load the config at `location`, run the checks and print the report after `prelude`.
+async fn run_once(
+    args: &CheckArgs,
+    location: &str,
+    prelude: &Report,
+    opts: &CheckOptions,
+    render: &RenderOptions,
+) -> (Report, ExitCode) {
+    if args.format == Format::Human && !args.quiet && args.output.is_none() {
+        print_header();
+    }
+    check_config(args, location, prelude, opts, render).await
+}
+
+/// Print the name and version of wakadoctor before a human readable report.
+fn print_header() {
+    println!("Wakadoctor - Test your wakatime configuration");
+    println!("Version {}", env!("CARGO_PKG_VERSION"));
+    println!();
+}
+
+/// Load the config at `location`, run the checks and print the report after `prelude`, without
+/// a header.
+async fn check_config(
+    args: &CheckArgs,
+    location: &str,
+    prelude: &Report,
+    opts: &CheckOptions,
+    render: &RenderOptions,
+) -> (Report, ExitCode) {
+    let report = collect_report(args, location, prelude, opts).await;
+    let code = print_report(&report, args, location, render);
+    (report, code)
+}
+
+/// Load the config at `location` and run the checks, returning the diagnostics selected with
+/// `--only` and `--skip` after `prelude`.
+async fn collect_report(
+    args: &CheckArgs,
+    location: &str,
+    prelude: &Report,
+    opts: &CheckOptions,
+) -> Report {
+    let mut report = prelude.clone();
+    let config = config::load_config(location, args.profile.as_deref(), &mut report);
+    // The checks scrub their own diagnostics, but these can quote the config too.
+    redact::scrub_report(
+        &mut report,
+        &[
+            opts.api_key.as_deref(),
+            config.as_ref().map(|v| v.settings.api_key.as_str()),
+        ],
+    );
+    if let Some(config) = config {
+        report.extend(wakadoctor::run_checks(&config, opts).await);
+    }
+    report.retain(|d| opts.selects(d.code));
+    report
+}
+
+/// Check each config at `locations` in turn, printing a section with its own summary for each
+/// that has output to show, then an overall summary.
+///
+/// The exit code is the one a single report with every diagnostic would have.
+async fn run_many(
+    args: &CheckArgs,
+    locations: &[String],
+    opts: &CheckOptions,
+    render: &RenderOptions,
+) -> ExitCode {
+    if args.format != Format::Human
+        || args.output.is_some()
+        || args.watch
+        || args.fix
+        || args.dump_config
+    {
+        eprintln!(
+            "Checking several configs only supports the human format, without --output, --watch, --fix or --dump-config."
         );
+        return ExitCode::FAILURE;
+    }
+    if locations.iter().filter(|v| *v == "-").count() > 1 {
+        eprintln!("Cannot read more than one config from stdin.");
+        return ExitCode::FAILURE;
     }
 
-    if config.settings.api_key.is_empty() {
-        println!("❌ - No API key in file");
-        return;
-    } else if host == WakaHost::Hackatime {
-        match uuid::Uuid::parse_str(&config.settings.api_key) {
-            Ok(_) => {
-                println!("✅ - Hackatime API key is in valid format");
-            }
-            Err(_) => {
-                println!("❌ - Hackatime API key is NOT in valid format");
-                return;
-            }
+    if !args.quiet {
+        print_header();
+    }
+    let mut all = Report::new();
+    for location in locations {
+        let name = if location == "-" { "stdin" } else { location };
+        let report = collect_report(args, location, &Report::new(), opts).await;
+        // With --quiet a config without problems renders nothing, so it gets no section either.
+        let rendered = output::render_human(&report, render);
+        if !rendered.is_empty() {
+            println!("== {name} ==");
+            print!("{rendered}");
+            println!();
         }
-    } else if host == WakaHost::Wakatime {
-        if config.settings.api_key.starts_with("waka_") {
-            match uuid::Uuid::parse_str(&config.settings.api_key.replacen("waka_", "", 1)) {
-                Ok(_) => {
-                    println!("✅ - Wakatime API key is in valid format");
-                }
-                Err(_) => {
-                    println!("❌ - Wakatime API key is NOT in valid format");
-                    return;
-                }
-            }
-        } else {
-            println!("❌ - Wakatime API key is NOT in valid format");
-            return;
+        all.extend(report);
+    }
+
+    let summary = Summary::of(&all);
+    println!("Overall ({} configs): {summary}", locations.len());
+    ExitCode::from(summary.exit_code(args.strict))
+}
+
+/// Run the checks, then run them again every time the config at `location` changes, until
+/// Ctrl-C.
+async fn watch(
+    args: &CheckArgs,
+    location: &str,
+    prelude: &Report,
+    opts: &CheckOptions,
+    render: &RenderOptions,
+) -> ExitCode {
+    use notify::Watcher;
+
+    if location == "-" {
+        eprintln!("Cannot watch a config read from stdin; pass its path with --config-location.");
+        return ExitCode::FAILURE;
+    }
+    let path = match paths::resolve_config_file(location) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Cannot find config file: {e}");
+            return ExitCode::FAILURE;
         }
+    };
+    let Some(file_name) = path.file_name().map(|v| v.to_owned()) else {
+        eprintln!("Cannot watch {}: not a file", path.display());
+        return ExitCode::FAILURE;
+    };
+    // Watch the directory rather than the file, so editors that save by renaming a new file over
+    // the old one keep being picked up.
+    let dir = match path.parent() {
+        Some(v) if !v.as_os_str().is_empty() => v.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = match notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    }) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Cannot watch config with error \"{e}\"");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(e) = watcher.watch(&dir, notify::RecursiveMode::NonRecursive) {
+        eprintln!("Cannot watch {} with error \"{e}\"", dir.display());
+        return ExitCode::FAILURE;
     }
 
+    loop {
+        run_once(args, location, prelude, opts, render).await;
+        eprintln!(
+            "\nWatching {} for changes (Ctrl-C to exit)...",
+            path.display()
+        );
 
-    if args.offline {
-        println!("⚠️ - Not attempting to perform online heartbeat check (--offline passed)")
-    } else {
-        match reqwest::Client::new()
-                    .post(if url.as_str().ends_with("/") {
-                        url.as_str().to_string() + "users/current/heartbeats"
-                    } else {
-                        url.as_str().to_string() + "/users/current/heartbeats"
-                    })
-                    .bearer_auth(config.settings.api_key)
-                    .body(format!(
-                        "[{{\"type\":\"file\",\"time\":{},\"entity\":\"wakadoctor-test.txt\",\"language\":\"Text\"}}]",
-                        time::UtcDateTime::now().unix_timestamp()
-                    ))
-                    .header("Content-Type", "application/json")
-                    .timeout(std::time::Duration::from_secs(10))
-                    .send()
-                    .await {
-            Ok(r) => {
-                if r.status().is_success() {
-                    println!("✅ - Got successful status code({})! {host} is configured correctly.", r.status());
-                } else {
-                    println!(
-                        "❌ - Got error status code ({}). {host} is NOT configured correctly.",
-                        r.status()
-                    );
-                }
-            },
-            Err(e) => {
-                if e.is_timeout() {
-                    println!(
-                        "❌ - Server timeout after 10 seconds. {host} is NOT configured correctly."
-                    );
-                } else {
-                    println!(
-                        "❌ - Got error status code ({}). {host} is NOT configured correctly.",
-                        e.status()
-                            .map(|v| v.as_str().to_string())
-                            .unwrap_or("no status code provided".to_string())
-                    );
+        loop {
+            let event = tokio::select! {
+                _ = tokio::signal::ctrl_c() => return ExitCode::SUCCESS,
+                event = rx.recv() => event,
+            };
+            match event {
+                // Reading the config ourselves produces access events, so ignore those.
+                Some(Ok(event))
+                    if !event.kind.is_access()
+                        && event
+                            .paths
+                            .iter()
+                            .any(|v| v.file_name() == Some(file_name.as_os_str())) =>
+                {
+                    break;
                 }
-                return;
-            },
-        };
+                Some(_) => {}
+                None => return ExitCode::FAILURE,
+            }
+        }
+        // Editors often write a file in several steps; wait for them to settle.
+        while let Ok(Some(_)) = tokio::time::timeout(WATCH_DEBOUNCE, rx.recv()).await {}
+
+        print!("\x1b[2J\x1b[H");
     }
-    
-    println!("✅ - {host} is configured correctly!");
 }
@@ -0,0 +1,359 @@
+//! Rendering a [`Report`] for output.
+
+use std::path::Path;
+
+use owo_colors::OwoColorize;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::diagnostic::{Diagnostic, DiagnosticCode, Level, Report, Summary};
+
+/// Options controlling how a [`Report`] is rendered.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct RenderOptions {
+    /// Only render warnings and errors.
+    pub quiet: bool,
+    /// Color lines by level with ANSI escapes.
+    pub color: bool,
+    /// Prefix lines with ASCII labels such as `[OK]` instead of emoji.
+    pub ascii: bool,
+    /// End human readable output with a table of each check and its status.
+    pub table: bool,
+}
+
+/// Whether to color output, from `NO_COLOR` and whether stdout is a terminal.
+pub fn auto_color() -> bool {
+    use std::io::IsTerminal;
+    std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && std::io::stdout().is_terminal()
+}
+
+/// Whether to use ASCII labels instead of emoji, because stdout is a terminal whose locale is not
+/// UTF-8.
+pub fn auto_ascii() -> bool {
+    use std::io::IsTerminal;
+    if !cfg!(unix) || !std::io::stdout().is_terminal() {
+        return false;
+    }
+    // The first of these that is set decides the character set, as in setlocale.
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|v| std::env::var(v).ok().filter(|v| !v.is_empty()))
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    !locale.contains("utf-8") && !locale.contains("utf8")
+}
+
+/// Color `text` according to `level`.
+fn paint(level: Level, text: &str) -> String {
+    match level {
+        Level::Ok => text.green().to_string(),
+        Level::Info => text.to_string(),
+        Level::Warning => text.yellow().to_string(),
+        Level::Error => text.red().to_string(),
+    }
+}
+
+/// The diagnostics of `report` to show: all of them, or only warnings and errors if `quiet`.
+fn shown(report: &Report, quiet: bool) -> Vec<&Diagnostic> {
+    report
+        .iter()
+        .filter(|d| !quiet || d.level.is_problem())
+        .collect()
+}
+
+/// Render `report` as human readable lines, followed by a summary line.
+///
+/// If quiet, only warnings and errors are rendered, and nothing at all if there are none.
+pub fn render_human(report: &Report, opts: &RenderOptions) -> String {
+    let shown = shown(report, opts.quiet);
+    if opts.quiet && shown.is_empty() {
+        return String::new();
+    }
+    let mut out = String::new();
+    for diagnostic in shown {
+        let line = diagnostic.line(opts.ascii);
+        if opts.color {
+            out += &paint(diagnostic.level, &line);
+        } else {
+            out += &line;
+        }
+        out.push('\n');
+    }
+    if opts.table {
+        out += &format!("\n{}", render_table(report, opts));
+    }
+    let summary = Summary::of(report);
+    if opts.color {
+        out += &format!("\n{}\n", paint(summary.level(), &summary.to_string()));
+    } else {
+        out += &format!("\n{summary}\n");
+    }
+    out
+}
+
+/// Word describing the status of a check at `level` in the table.
+fn status(level: Level) -> &'static str {
+    match level {
+        Level::Ok => "passed",
+        Level::Info => "info",
+        Level::Warning => "warning",
+        Level::Error => "failed",
+    }
+}
+
+/// Render a two-column table of each check in `report`, by code in the order they first ran, and
+/// its most severe level.
+///
+/// If quiet, only checks with warnings or errors are listed.
+fn render_table(report: &Report, opts: &RenderOptions) -> String {
+    let mut checks: Vec<(DiagnosticCode, Level)> = Vec::new();
+    for diagnostic in shown(report, opts.quiet) {
+        match checks.iter_mut().find(|(code, _)| *code == diagnostic.code) {
+            Some((_, level)) => *level = (*level).max(diagnostic.level),
+            None => checks.push((diagnostic.code, diagnostic.level)),
+        }
+    }
+    let width = checks
+        .iter()
+        .map(|(code, _)| code.as_str().len())
+        .chain(["Check".len()])
+        .max()
+        .unwrap_or(0);
+
+    let mut out = format!(
+        "{:width$} | Status\n{}-+-{}\n",
+        "Check",
+        "-".repeat(width),
+        "-".repeat(12)
+    );
+    for (code, level) in checks {
+        let prefix = if opts.ascii {
+            level.label()
+        } else {
+            level.emoji()
+        };
+        let status = format!("{prefix} {}", status(level));
+        let status = if opts.color {
+            paint(level, &status)
+        } else {
+            status
+        };
+        out += &format!("{code:width$} | {status}\n");
+    }
+    out
+}
+
+/// Render `report` as a Test Anything Protocol stream, with one test per diagnostic followed by
+/// the plan.
+///
+/// Warnings are `not ok` with a `TODO` directive, so they show up without failing the run. If
+/// quiet, only warnings and errors are included.
+pub fn render_tap(report: &Report, opts: &RenderOptions) -> String {
+    let shown = shown(report, opts.quiet);
+    let mut out = "TAP version 13\n".to_string();
+    for (i, diagnostic) in shown.iter().enumerate() {
+        // `#` starts a directive, so it must be escaped in the description.
+        let description =
+            format!("[{}] {}", diagnostic.code, diagnostic.message).replace('#', "\\#");
+        out += &match diagnostic.level {
+            Level::Ok | Level::Info => format!("ok {} - {description}\n", i + 1),
+            Level::Warning => format!("not ok {} - {description} # TODO warning\n", i + 1),
+            Level::Error => format!("not ok {} - {description}\n", i + 1),
+        };
+    }
+    out += &format!("1..{}\n", shown.len());
+    out
+}
+
+/// Escape `text` for use in XML text and attribute values.
+fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out += "&amp;",
+            '<' => out += "&lt;",
+            '>' => out += "&gt;",
+            '"' => out += "&quot;",
+            '\'' => out += "&apos;",
+            // Control characters other than tab and newlines are not allowed in XML 1.0 at all.
+            c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Render `report` as a JUnit XML `<testsuite>`, with one `<testcase>` per diagnostic named after
+/// its code.
+///
+/// Errors are `<failure>`s and warnings are `<skipped>`. If quiet, only warnings and errors are
+/// included.
+pub fn render_junit(report: &Report, opts: &RenderOptions) -> String {
+    let shown = shown(report, opts.quiet);
+    let count = |level| shown.iter().filter(|d| d.level == level).count();
+    let mut out = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuite name=\"wakadoctor\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\">\n",
+        shown.len(),
+        count(Level::Error),
+        count(Level::Warning),
+    );
+    for diagnostic in shown {
+        let name = escape_xml(diagnostic.code.as_str());
+        let message = escape_xml(&diagnostic.message);
+        out += &match diagnostic.level {
+            Level::Ok | Level::Info => format!(
+                "  <testcase classname=\"wakadoctor\" name=\"{name}\">\n    <system-out>{message}</system-out>\n  </testcase>\n"
+            ),
+            Level::Warning => format!(
+                "  <testcase classname=\"wakadoctor\" name=\"{name}\">\n    <skipped message=\"{message}\"/>\n  </testcase>\n"
+            ),
+            Level::Error => format!(
+                "  <testcase classname=\"wakadoctor\" name=\"{name}\">\n    <failure message=\"{message}\">{message}</failure>\n  </testcase>\n"
+            ),
+        };
+    }
+    out += "</testsuite>\n";
+    out
+}
+
+/// The JSON document produced by [`render_json`].
+#[derive(Serialize, JsonSchema)]
+struct JsonReport<'a> {
+    /// Every shown diagnostic, in order.
+    diagnostics: Vec<&'a Diagnostic>,
+    /// Counts of the diagnostics.
+    summary: JsonSummary,
+}
+
+/// Summary section of [`JsonReport`].
+#[derive(Serialize, JsonSchema)]
+struct JsonSummary {
+    /// Counts by level.
+    #[serde(flatten)]
+    counts: Summary,
+    /// Whether there were no errors.
+    ok: bool,
+}
+
+/// Render `report` as a JSON document.
+///
+/// If quiet, only warnings and errors are included; the summary still counts everything.
+pub fn render_json(report: &Report, opts: &RenderOptions) -> String {
+    let summary = Summary::of(report);
+    serde_json::to_string_pretty(&JsonReport {
+        diagnostics: shown(report, opts.quiet),
+        summary: JsonSummary {
+            counts: summary,
+            ok: summary.ok(),
+        },
+    })
+    .unwrap()
+}
+
+/// Render `report` as a SARIF 2.1.0 log with one result per diagnostic, each located in the
+/// config file at `config`, if it was read from a file.
+///
+/// Errors and warnings are failures of the SARIF levels `error` and `warning`, infos are
+/// results of kind `informational` and passed checks of kind `pass`. Each code is a rule,
+/// described by its explanation. If quiet, only warnings and errors are included.
+pub fn render_sarif(report: &Report, opts: &RenderOptions, config: Option<&Path>) -> String {
+    let shown = shown(report, opts.quiet);
+    let mut codes = Vec::new();
+    for diagnostic in &shown {
+        if !codes.contains(&diagnostic.code) {
+            codes.push(diagnostic.code);
+        }
+    }
+    let rules = codes
+        .iter()
+        .map(|code| {
+            serde_json::json!({
+                "id": code,
+                "fullDescription": { "text": crate::explain::explanation(*code) },
+            })
+        })
+        .collect::<Vec<_>>();
+    let location = config
+        .and_then(|v| std::path::absolute(v).ok())
+        .and_then(|v| url::Url::from_file_path(v).ok());
+    let results = shown
+        .iter()
+        .map(|d| {
+            let (kind, level) = match d.level {
+                // Only failures may have a level other than `none`.
+                Level::Ok => ("pass", "none"),
+                Level::Info => ("informational", "none"),
+                Level::Warning => ("fail", "warning"),
+                Level::Error => ("fail", "error"),
+            };
+            let mut result = serde_json::json!({
+                "ruleId": d.code,
+                "kind": kind,
+                "level": level,
+                "message": { "text": d.message },
+            });
+            if let Some(uri) = &location {
+                result["locations"] = serde_json::json!([{
+                    "physicalLocation": { "artifactLocation": { "uri": uri.as_str() } }
+                }]);
+            }
+            result
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::to_string_pretty(&serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "wakadoctor",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }],
+    }))
+    .unwrap()
+}
+
+/// The JSON Schema of the documents produced by [`render_json`].
+pub fn json_schema() -> String {
+    serde_json::to_string_pretty(&schemars::schema_for!(JsonReport<'static>)).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_xml() {
+        assert_eq!(
+            escape_xml("<a href=\"x\">Tom & 'Jerry'</a>\u{1b}"),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; &apos;Jerry&apos;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn tables() {
+        let report = vec![
+            Diagnostic::ok(DiagnosticCode::ConfigRead, "a"),
+            Diagnostic::info(DiagnosticCode::DryRun, "b"),
+            Diagnostic::warning(DiagnosticCode::DryRun, "c"),
+            Diagnostic::error(DiagnosticCode::KeyInvalid, "d"),
+        ];
+        let opts = RenderOptions {
+            ascii: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_table(&report, &opts),
+            "Check       | Status\n\
+             ------------+-------------\n\
+             config_read | [OK] passed\n\
+             dry_run     | [WARN] warning\n\
+             key_invalid | [FAIL] failed\n"
+        );
+    }
+}
@@ -0,0 +1,198 @@
+//! Path helpers.
+
+use std::path::{Path, PathBuf};
+
+/// Name of the wakatime config file.
+pub const CONFIG_FILE_NAME: &str = ".wakatime.cfg";
+
+/// Config file names looked for when the config location is a directory, in order.
+pub const CONFIG_DIR_FILE_NAMES: &[&str] = &[CONFIG_FILE_NAME, "wakatime.cfg"];
+
+/// Find the config file in the directory `dir`.
+///
+/// Returns the paths searched if none of [`CONFIG_DIR_FILE_NAMES`] exist.
+pub fn find_config_in_dir(dir: &Path) -> Result<PathBuf, Vec<PathBuf>> {
+    let candidates = CONFIG_DIR_FILE_NAMES
+        .iter()
+        .map(|v| dir.join(v))
+        .collect::<Vec<_>>();
+    match candidates.iter().find(|v| v.is_file()) {
+        Some(v) => Ok(v.clone()),
+        None => Err(candidates),
+    }
+}
+
+/// Resolve a config location to a file: expand `~`, and look inside directories.
+pub fn resolve_config_file(location: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(expand_home(location)?);
+    if !path.is_dir() {
+        return Ok(path);
+    }
+    find_config_in_dir(&path).map_err(|searched| {
+        format!(
+            "{} is a directory containing none of {}",
+            path.display(),
+            searched
+                .iter()
+                .map(|v| v.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    })
+}
+
+/// Config location used when none was passed on the command line.
+///
+/// Mirrors wakatime-cli: `$WAKATIME_HOME/.wakatime.cfg` if `WAKATIME_HOME` is set, otherwise
+/// `~/.wakatime.cfg`. The second value is `WAKATIME_HOME` when it was used.
+pub fn default_config_location() -> (String, Option<String>) {
+    default_config_location_with(std::env::var("WAKATIME_HOME").ok())
+}
+
+/// Same as [`default_config_location`], but with `WAKATIME_HOME` supplied by the caller.
+fn default_config_location_with(wakatime_home: Option<String>) -> (String, Option<String>) {
+    match wakatime_home.filter(|v| !v.is_empty()) {
+        Some(home) => (
+            Path::new(&home)
+                .join(CONFIG_FILE_NAME)
+                .to_string_lossy()
+                .into_owned(),
+            Some(home),
+        ),
+        None => (format!("~/{CONFIG_FILE_NAME}"), None),
+    }
+}
+
+/// Directories wakatime-cli keeps its state files in: next to the config at `config_path`, then
+/// `~/.wakatime/`.
+pub fn state_dirs(config_path: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(dir) = config_path.parent() {
+        dirs.push(dir.to_path_buf());
+    }
+    if let Some(home) = dirs::home_dir() {
+        dirs.push(home.join(".wakatime"));
+    }
+    dirs
+}
+
+/// Known hosts file used when none was passed on the command line, e.g.
+/// `~/.config/wakadoctor/known_hosts`.
+pub fn default_known_hosts_file() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("wakadoctor").join("known_hosts"))
+}
+
+/// File caching the latest wakadoctor version for `--check-update`, e.g.
+/// `~/.cache/wakadoctor/latest_version`.
+pub fn update_cache_file() -> Option<PathBuf> {
+    Some(dirs::cache_dir()?.join("wakadoctor").join("latest_version"))
+}
+
+/// File holding the profile `name`, e.g. `~/.config/wakadoctor/profiles/work.cfg`.
+pub fn profile_file(name: &str) -> Option<PathBuf> {
+    Some(
+        dirs::config_dir()?
+            .join("wakadoctor")
+            .join("profiles")
+            .join(format!("{name}.cfg")),
+    )
+}
+
+/// Expand a leading `~` or `~/` in `path` to the current user's home directory.
+///
+/// Only the leading tilde is touched, so `~/a/~b` becomes `<home>/a/~b`.
+pub fn expand_home(path: &str) -> Result<String, String> {
+    expand_home_with(path, dirs::home_dir())
+}
+
+/// Same as [`expand_home`], but with the home directory supplied by the caller.
+pub(crate) fn expand_home_with(path: &str, home: Option<PathBuf>) -> Result<String, String> {
+    let rest = if path == "~" {
+        ""
+    } else if let Some(rest) = path.strip_prefix("~/") {
+        rest
+    } else {
+        return Ok(path.to_string());
+    };
+
+    let Some(home) = home else {
+        return Err("Could not determine home directory".to_string());
+    };
+
+    if rest.is_empty() {
+        Ok(home.to_string_lossy().into_owned())
+    } else {
+        Ok(home.join(rest).to_string_lossy().into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_config_in_dir() {
+        let dir = std::env::temp_dir().join(format!("wakadoctor-dir-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert_eq!(
+            find_config_in_dir(&dir),
+            Err(vec![dir.join(".wakatime.cfg"), dir.join("wakatime.cfg")])
+        );
+        std::fs::write(dir.join("wakatime.cfg"), "").unwrap();
+        assert_eq!(find_config_in_dir(&dir), Ok(dir.join("wakatime.cfg")));
+        std::fs::write(dir.join(".wakatime.cfg"), "").unwrap();
+        assert_eq!(find_config_in_dir(&dir), Ok(dir.join(".wakatime.cfg")));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// Home directory used by the tests.
+    fn home() -> Option<PathBuf> {
+        Some(PathBuf::from("/home/user"))
+    }
+
+    #[test]
+    fn leading_tilde() {
+        assert_eq!(
+            expand_home_with("~/projects/~weird/.wakatime.cfg", home()).unwrap(),
+            "/home/user/projects/~weird/.wakatime.cfg"
+        );
+    }
+
+    #[test]
+    fn bare_tilde() {
+        assert_eq!(expand_home_with("~", home()).unwrap(), "/home/user");
+    }
+
+    #[test]
+    fn no_tilde() {
+        assert_eq!(
+            expand_home_with("/etc/~wakatime.cfg", home()).unwrap(),
+            "/etc/~wakatime.cfg"
+        );
+        assert_eq!(expand_home_with("~user/x", None).unwrap(), "~user/x");
+    }
+
+    #[test]
+    fn wakatime_home() {
+        assert_eq!(
+            default_config_location_with(Some("/opt/waka".to_string())),
+            (
+                "/opt/waka/.wakatime.cfg".to_string(),
+                Some("/opt/waka".to_string())
+            )
+        );
+        assert_eq!(
+            default_config_location_with(Some(String::new())),
+            ("~/.wakatime.cfg".to_string(), None)
+        );
+        assert_eq!(
+            default_config_location_with(None),
+            ("~/.wakatime.cfg".to_string(), None)
+        );
+    }
+
+    #[test]
+    fn no_home_dir() {
+        assert!(expand_home_with("~/.wakatime.cfg", None).is_err());
+    }
+}
@@ -0,0 +1,77 @@
+//! Checking who can read the config file.
+
+use std::path::Path;
+
+use crate::diagnostic::Report;
+
+/// Warn if the config at `path`, which holds the API key, is readable by every user.
+///
+/// Only Unix file modes are checked; elsewhere this does nothing.
+#[cfg(unix)]
+pub fn check_permissions(path: &Path, report: &mut Report) {
+    use std::os::unix::fs::PermissionsExt;
+
+    use crate::{
+        diagnostic::{Diagnostic, DiagnosticCode},
+        fix::Fix,
+    };
+
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return;
+    };
+    let mode = metadata.permissions().mode() & 0o777;
+    if mode & 0o004 != 0 {
+        report.push(
+            Diagnostic::warning(
+                DiagnosticCode::ConfigPermissions,
+                format!(
+                    "Config file is world-readable (mode {mode:04o}); consider chmod 600 to protect your API key"
+                ),
+            )
+            .with_fix(Fix::RestrictPermissions),
+        );
+    } else {
+        report.push(Diagnostic::ok(
+            DiagnosticCode::ConfigPermissions,
+            format!("Config file is not world-readable (mode {mode:04o})"),
+        ));
+    }
+}
+
+/// Warn if the config at `path`, which holds the API key, is readable by every user.
+///
+/// Only Unix file modes are checked; elsewhere this does nothing.
+#[cfg(not(unix))]
+pub fn check_permissions(_path: &Path, _report: &mut Report) {}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+
+    #[test]
+    fn world_readable() {
+        let path =
+            std::env::temp_dir().join(format!("wakadoctor-permissions-{}.cfg", std::process::id()));
+        std::fs::write(&path, "[settings]\n").unwrap();
+        let messages = |mode| {
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+            let mut report = Report::new();
+            check_permissions(&path, &mut report);
+            report
+                .into_iter()
+                .map(|d| (d.level, d.message))
+                .collect::<Vec<_>>()
+        };
+
+        let found = messages(0o644);
+        assert_eq!(found[0].0, crate::diagnostic::Level::Warning);
+        assert_eq!(
+            found[0].1,
+            "Config file is world-readable (mode 0644); consider chmod 600 to protect your API key"
+        );
+        assert_eq!(messages(0o600)[0].0, crate::diagnostic::Level::Ok);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
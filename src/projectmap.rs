@@ -0,0 +1,101 @@
+//! Checks of the `[projectmap]` section.
+
+use std::collections::BTreeMap;
+
+use crate::diagnostic::{Diagnostic, DiagnosticCode, Report};
+
+/// Check every key of `projectmap` compiles as a regex and every `{N}` in its project name refers
+/// to a capture group of that regex.
+pub fn check_projectmap(projectmap: &BTreeMap<String, String>, report: &mut Report) {
+    if projectmap.is_empty() {
+        return;
+    }
+
+    let mut valid = 0;
+    for (pattern, project) in projectmap {
+        let regex = match regex::Regex::new(pattern) {
+            Ok(v) => v,
+            Err(e) => {
+                // The full error spans several lines; its last line names the problem.
+                let e = e.to_string();
+                let reason = e.lines().last().unwrap_or_default();
+                report.push(Diagnostic::error(
+                    DiagnosticCode::ProjectmapInvalid,
+                    format!(
+                        "Invalid projectmap pattern \"{pattern}\" ({})",
+                        reason.trim_start_matches("error: ")
+                    ),
+                ));
+                continue;
+            }
+        };
+        // Group 0 of the regex is the whole match, so `{0}` is the first capture group.
+        let groups = regex.captures_len() - 1;
+        if let Some(n) = placeholders(project).find(|n| *n >= groups) {
+            report.push(Diagnostic::error(
+                DiagnosticCode::ProjectmapInvalid,
+                format!(
+                    "projectmap project \"{project}\" uses {{{n}}}, but \"{pattern}\" has {groups} capture group(s)"
+                ),
+            ));
+            continue;
+        }
+        valid += 1;
+    }
+    if valid > 0 {
+        report.push(Diagnostic::ok(
+            DiagnosticCode::Projectmap,
+            format!("projectmap has {valid} valid mapping(s)"),
+        ));
+    }
+}
+
+/// Numbers of the `{N}` placeholders in `project`.
+fn placeholders(project: &str) -> impl Iterator<Item = usize> + '_ {
+    project
+        .split('{')
+        .skip(1)
+        .filter_map(|v| v.split_once('}')?.0.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mappings() {
+        let projectmap = [
+            ("^/work/([^/]+)/", "work-{0}"),
+            ("^/home/(unclosed", "home"),
+            ("^/code/", "code-{0}"),
+            ("^/misc/", "misc"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let mut report = Report::new();
+        check_projectmap(&projectmap, &mut report);
+        let found = report
+            .iter()
+            .map(|d| (d.code.as_str(), d.message.as_str()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(found.len(), 3);
+        assert_eq!(
+            found[0],
+            (
+                "projectmap_invalid",
+                "projectmap project \"code-{0}\" uses {0}, but \"^/code/\" has 0 capture group(s)"
+            )
+        );
+        assert!(
+            found[1]
+                .1
+                .starts_with("Invalid projectmap pattern \"^/home/(unclosed\"")
+        );
+        assert_eq!(
+            found[2],
+            ("projectmap", "projectmap has 2 valid mapping(s)")
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Checks of wakatime-cli's offline heartbeat queue.
+
+use std::path::Path;
+
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticCode, Report},
+    paths,
+};
+
+/// Names of the offline queue database.
+const QUEUE_FILE_NAMES: &[&str] = &[".wakatime.bdb", "offline_heartbeats.bdb"];
+
+/// Number of queued heartbeats above which the queue is reported as large.
+const LARGE_QUEUE: usize = 1000;
+
+/// Approximate number of heartbeats in the offline queue database `data`.
+///
+/// The queue is a BoltDB file of JSON heartbeats, so this counts heartbeat objects in the raw
+/// bytes. Freed pages can still hold old heartbeats, so this may overcount.
+fn count_heartbeats(data: &[u8]) -> usize {
+    let needle = b"\"entity\":";
+    data.windows(needle.len()).filter(|v| v == needle).count()
+}
+
+/// Format `n` with thousands separators, e.g. `4,312`.
+fn thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Report the size of the offline heartbeat queue for the config at `config_path`.
+pub fn check_queue(config_path: &Path, report: &mut Report) {
+    let Some(path) = paths::state_dirs(config_path)
+        .into_iter()
+        .flat_map(|dir| QUEUE_FILE_NAMES.iter().map(move |v| dir.join(v)))
+        .find(|v| v.is_file())
+    else {
+        return;
+    };
+
+    let data = match std::fs::read(&path) {
+        Ok(v) => v,
+        Err(e) => {
+            report.push(Diagnostic::info(
+                DiagnosticCode::OfflineQueue,
+                format!(
+                    "Cannot read offline heartbeat queue {} with error \"{e}\"",
+                    path.display()
+                ),
+            ));
+            return;
+        }
+    };
+
+    let count = count_heartbeats(&data);
+    let size = data.len().div_ceil(1024);
+    if count > LARGE_QUEUE {
+        report.push(Diagnostic::warning(
+            DiagnosticCode::OfflineQueue,
+            format!(
+                "{} heartbeats are queued offline ({} KB); your server may be unreachable",
+                thousands(count),
+                thousands(size)
+            ),
+        ));
+    } else {
+        report.push(Diagnostic::ok(
+            DiagnosticCode::OfflineQueue,
+            format!(
+                "About {} heartbeat(s) are queued offline ({} KB)",
+                thousands(count),
+                thousands(size)
+            ),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_heartbeats() {
+        let data = b"\0\0{\"entity\":\"a.rs\",\"time\":1}\x07{\"entity\":\"b.rs\"}\0";
+        assert_eq!(count_heartbeats(data), 2);
+        assert_eq!(count_heartbeats(b"\0\0\0"), 0);
+    }
+
+    #[test]
+    fn formats_thousands() {
+        assert_eq!(thousands(0), "0");
+        assert_eq!(thousands(999), "999");
+        assert_eq!(thousands(4312), "4,312");
+        assert_eq!(thousands(1234567), "1,234,567");
+    }
+}
@@ -0,0 +1,69 @@
+//! Keeping API keys out of output.
+
+use crate::diagnostic::Report;
+
+/// Keys shorter than this are hidden entirely, as their first 8 characters would give away most
+/// of the key; real keys are far longer.
+const MIN_PREFIXED_LEN: usize = 16;
+
+/// Hide all of `key` but a recognizable prefix: `waka_` for Wakatime keys, or the first 8
+/// characters of keys of at least [`MIN_PREFIXED_LEN`] otherwise.
+pub fn redact(key: &str) -> String {
+    let prefix = if key.starts_with("waka_") {
+        "waka_"
+    } else if key.len() >= MIN_PREFIXED_LEN {
+        key.get(..8).unwrap_or_default()
+    } else {
+        ""
+    };
+    format!("{prefix}****")
+}
+
+/// Replace every occurrence of each non-empty one of `keys` in `text` with its [`redact`]ed form.
+pub fn scrub<'a>(text: &str, keys: impl IntoIterator<Item = &'a str>) -> String {
+    let mut text = text.to_string();
+    for key in keys {
+        let key = key.trim();
+        if !key.is_empty() {
+            text = text.replace(key, &redact(key));
+        }
+    }
+    text
+}
+
+/// [`scrub`] `keys` from the message of every diagnostic in `report`.
+pub fn scrub_report(report: &mut Report, keys: &[Option<&str>]) {
+    for diagnostic in report {
+        diagnostic.message = scrub(&diagnostic.message, keys.iter().copied().flatten());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_keys() {
+        assert_eq!(
+            redact("waka_3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e"),
+            "waka_****"
+        );
+        assert_eq!(
+            redact("3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e"),
+            "3f1c3c3e****"
+        );
+        assert_eq!(redact("short"), "****");
+        assert_eq!(redact("hunter2hunter2"), "****");
+        assert_eq!(
+            scrub(
+                "stderr: \"bad key 3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e\"",
+                ["3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e", ""]
+            ),
+            "stderr: \"bad key 3f1c3c3e****\""
+        );
+        assert_eq!(
+            scrub("stderr: \"hunter2\"", [" hunter2 "]),
+            "stderr: \"****\""
+        );
+    }
+}
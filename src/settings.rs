@@ -0,0 +1,599 @@
+//! Checks of individual `[settings]` values.
+
+use std::path::Path;
+
+use crate::{
+    checks::CheckOptions,
+    config::{self, WakaSettings},
+    diagnostic::{Diagnostic, DiagnosticCode, Report},
+    paths,
+};
+
+/// Run every settings check against `settings`.
+pub fn check_settings(settings: &WakaSettings, opts: &CheckOptions, report: &mut Report) {
+    check_bools(settings, report);
+    if config::is_enabled(&settings.debug) {
+        check_debug(&settings.log_file, report);
+    }
+    if !settings.log_file.trim().is_empty() {
+        check_log_file(settings.log_file.trim(), report);
+    }
+    check_timeout(&settings.timeout, report);
+    check_rate_limit(&settings.heartbeat_rate_limit_seconds, report);
+    if let Some(hostname) = &settings.hostname {
+        check_hostname(hostname, report);
+    }
+    check_patterns("exclude", &settings.exclude, report);
+    check_patterns("include", &settings.include, report);
+    check_project_filter(
+        DiagnosticCode::ExcludeUnknownProject,
+        &settings.exclude_unknown_project,
+        "heartbeats for files outside a detected project are dropped",
+        report,
+    );
+    check_project_filter(
+        DiagnosticCode::IncludeOnlyWithProjectFile,
+        &settings.include_only_with_project_file,
+        "only files in a folder with a .wakatime-project file (or below one) are tracked",
+        report,
+    );
+    check_status_bar(
+        DiagnosticCode::StatusBarEnabled,
+        &settings.status_bar_enabled,
+        "editor plugins do not show wakatime in the status bar",
+        report,
+    );
+    check_status_bar(
+        DiagnosticCode::StatusBarCodingActivity,
+        &settings.status_bar_coding_activity,
+        "the status bar does not show today's coding time",
+        report,
+    );
+    if !opts.no_privacy_check {
+        check_privacy(settings, report);
+    }
+}
+
+/// Warn about each boolean setting whose value is not a boolean.
+fn check_bools(settings: &WakaSettings, report: &mut Report) {
+    for (key, value) in [
+        ("debug", &settings.debug),
+        ("hide_file_names", &settings.hide_file_names),
+        ("hide_project_names", &settings.hide_project_names),
+        ("hide_branch_names", &settings.hide_branch_names),
+        ("hide_dependencies", &settings.hide_dependencies),
+        ("hide_project_folder", &settings.hide_project_folder),
+        ("no_ssl_verify", &settings.no_ssl_verify),
+    ] {
+        if config::parse_bool(value).is_none() {
+            report.push(Diagnostic::warning(
+                DiagnosticCode::BoolInvalid,
+                format!(
+                    "{key} must be true or false, not \"{}\"; it is treated as false",
+                    value.trim()
+                ),
+            ));
+        }
+    }
+}
+
+/// Warn that `debug` is enabled, naming `log_file` if set.
+fn check_debug(log_file: &str, report: &mut Report) {
+    let mut message =
+        "debug mode is enabled; this writes verbose logs and may impact performance".to_string();
+    let log_file = log_file.trim();
+    if !log_file.is_empty() {
+        message.push_str(&format!(" (logs go to {log_file})"));
+    }
+    report.push(Diagnostic::warning(DiagnosticCode::DebugEnabled, message));
+}
+
+/// Check the directory of `log_file` exists and the file can be appended to.
+///
+/// The file is opened for appending, creating it if needed; a file created here is removed again.
+fn check_log_file(log_file: &str, report: &mut Report) {
+    let path = match paths::expand_home(log_file) {
+        Ok(v) => v,
+        Err(e) => {
+            report.push(Diagnostic::error(
+                DiagnosticCode::LogFileUnwritable,
+                format!("Cannot expand log_file location with error \"{e}\""),
+            ));
+            return;
+        }
+    };
+    let path = Path::new(&path);
+    let dir = match path.parent() {
+        Some(v) if !v.as_os_str().is_empty() => v,
+        _ => Path::new("."),
+    };
+    if !dir.is_dir() {
+        report.push(Diagnostic::error(
+            DiagnosticCode::LogFileDirMissing,
+            format!("log_file directory does not exist: {}", dir.display()),
+        ));
+        return;
+    }
+
+    let existed = path.exists();
+    match std::fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)
+    {
+        Ok(_) => {
+            if !existed {
+                let _ = std::fs::remove_file(path);
+            }
+            report.push(Diagnostic::ok(
+                DiagnosticCode::LogFile,
+                format!("log_file {} is writable", path.display()),
+            ));
+        }
+        Err(_) => report.push(Diagnostic::error(
+            DiagnosticCode::LogFileUnwritable,
+            format!("log_file directory is not writable: {}", path.display()),
+        )),
+    }
+}
+
+/// Check `timeout` is a positive integer.
+fn check_timeout(timeout: &str, report: &mut Report) {
+    let timeout = timeout.trim();
+    if timeout.is_empty() {
+        return;
+    }
+    match timeout.parse::<i64>() {
+        Ok(0) => report.push(Diagnostic::warning(
+            DiagnosticCode::TimeoutZero,
+            "timeout of 0 disables the request timeout",
+        )),
+        Ok(v) if v > 0 => report.push(Diagnostic::ok(
+            DiagnosticCode::Timeout,
+            format!("timeout of {v} seconds is valid"),
+        )),
+        _ => report.push(Diagnostic::error(
+            DiagnosticCode::TimeoutInvalid,
+            format!("timeout must be a positive integer, not \"{timeout}\""),
+        )),
+    }
+}
+
+/// Note that the project filter `key` is on with `value`, saying it means `filtering`, or warn
+/// that `value` is not a boolean.
+fn check_project_filter(key: DiagnosticCode, value: &str, filtering: &str, report: &mut Report) {
+    match config::parse_bool(value) {
+        Some(true) => report.push(Diagnostic::info(
+            key,
+            format!("{key} is enabled: {filtering}, so some activity may be missing"),
+        )),
+        Some(false) => (),
+        None => report.push(Diagnostic::warning(
+            key,
+            format!("{key} must be true or false, not \"{}\"", value.trim()),
+        )),
+    }
+}
+
+/// Note that the status bar setting `key` is off with `value`, saying it means `hidden`, or warn
+/// that `value` is not a boolean. An empty value is the default, which is on.
+fn check_status_bar(key: DiagnosticCode, value: &str, hidden: &str, report: &mut Report) {
+    if value.trim().is_empty() {
+        return;
+    }
+    match config::parse_bool(value) {
+        Some(true) => (),
+        Some(false) => report.push(Diagnostic::info(
+            key,
+            format!("{key} is false: {hidden}, though time is still tracked"),
+        )),
+        None => report.push(Diagnostic::warning(
+            key,
+            format!("{key} must be true or false, not \"{}\"", value.trim()),
+        )),
+    }
+}
+
+/// `heartbeat_rate_limit_seconds` below which heartbeats are sent often enough to load the
+/// server; wakatime-cli defaults to 120.
+const MIN_RATE_LIMIT: u64 = 30;
+
+/// `heartbeat_rate_limit_seconds` above which so few heartbeats are sent that time goes untracked.
+const MAX_RATE_LIMIT: u64 = 600;
+
+/// Describe `seconds` in the largest whole unit, e.g. `hour` for 3600 or `90 seconds` for 90.
+fn per_duration(seconds: u64) -> String {
+    let (amount, unit) = if seconds.is_multiple_of(3600) {
+        (seconds / 3600, "hour")
+    } else if seconds.is_multiple_of(60) {
+        (seconds / 60, "minute")
+    } else {
+        (seconds, "second")
+    };
+    if amount == 1 {
+        unit.to_string()
+    } else {
+        format!("{amount} {unit}s")
+    }
+}
+
+/// Check `heartbeat_rate_limit_seconds` is a number of seconds within a sensible range.
+fn check_rate_limit(rate_limit: &str, report: &mut Report) {
+    let rate_limit = rate_limit.trim();
+    if rate_limit.is_empty() {
+        return;
+    }
+    match rate_limit.parse::<u64>() {
+        Ok(0) => report.push(Diagnostic::warning(
+            DiagnosticCode::HeartbeatRateLimitExtreme,
+            "heartbeat_rate_limit_seconds of 0 disables rate limiting, sending a heartbeat on every editor event",
+        )),
+        Ok(v) if v < MIN_RATE_LIMIT => report.push(Diagnostic::warning(
+            DiagnosticCode::HeartbeatRateLimitExtreme,
+            format!(
+                "heartbeat_rate_limit_seconds of {v} sends heartbeats very often and may hammer the server"
+            ),
+        )),
+        Ok(v) if v > MAX_RATE_LIMIT => report.push(Diagnostic::warning(
+            DiagnosticCode::HeartbeatRateLimitExtreme,
+            format!(
+                "heartbeat_rate_limit_seconds of {v} means at most one heartbeat per {}",
+                per_duration(v)
+            ),
+        )),
+        Ok(v) => report.push(Diagnostic::ok(
+            DiagnosticCode::HeartbeatRateLimit,
+            format!("heartbeat_rate_limit_seconds of {v} is sensible"),
+        )),
+        Err(_) => report.push(Diagnostic::error(
+            DiagnosticCode::HeartbeatRateLimitInvalid,
+            format!(
+                "heartbeat_rate_limit_seconds must be a whole number of seconds, not \"{rate_limit}\""
+            ),
+        )),
+    }
+}
+
+/// Check `hostname` is a plausible hostname: not blank, and only letters, digits, `-` and `.`.
+fn check_hostname(hostname: &str, report: &mut Report) {
+    if hostname.trim().is_empty() {
+        report.push(Diagnostic::warning(
+            DiagnosticCode::HostnameEmpty,
+            "hostname is set but empty; heartbeats will be attributed to a blank machine",
+        ));
+    } else if let Some(c) = hostname
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && !matches!(c, '-' | '.'))
+    {
+        report.push(Diagnostic::warning(
+            DiagnosticCode::HostnameInvalid,
+            format!("hostname \"{hostname}\" contains {c:?}, which is not valid in a hostname"),
+        ));
+    } else {
+        report.push(Diagnostic::ok(
+            DiagnosticCode::Hostname,
+            format!("hostname \"{hostname}\" is valid"),
+        ));
+    }
+}
+
+/// Check every line of the `name` setting compiles as a regex.
+fn check_patterns(name: &str, value: &str, report: &mut Report) {
+    let lines = config::value_lines(value);
+    if lines.is_empty() {
+        return;
+    }
+
+    let mut valid = 0;
+    for (i, pattern) in lines.iter().enumerate() {
+        match regex::Regex::new(pattern) {
+            Ok(_) => valid += 1,
+            Err(e) => {
+                // The full error spans several lines; its last line names the problem.
+                let e = e.to_string();
+                let reason = e.lines().last().unwrap_or_default();
+                report.push(Diagnostic::error(
+                    DiagnosticCode::PatternInvalid,
+                    format!(
+                        "Invalid {name} pattern on line {}: \"{pattern}\" ({})",
+                        i + 1,
+                        reason.trim_start_matches("error: ")
+                    ),
+                ));
+            }
+        }
+    }
+    if valid > 0 {
+        report.push(Diagnostic::ok(
+            DiagnosticCode::Patterns,
+            format!("{name} has {valid} valid pattern(s)"),
+        ));
+    }
+}
+
+/// Warn about `hide_*` flags that are set without the flags needed to make them effective.
+fn check_privacy(settings: &WakaSettings, report: &mut Report) {
+    let flag = |name, value: &str| (name, config::is_enabled(value));
+    let file_names = flag("hide_file_names", &settings.hide_file_names);
+    let project_names = flag("hide_project_names", &settings.hide_project_names);
+    let branch_names = flag("hide_branch_names", &settings.hide_branch_names);
+    let dependencies = flag("hide_dependencies", &settings.hide_dependencies);
+    let project_folder = flag("hide_project_folder", &settings.hide_project_folder);
+    // The flag that is set, the flag that is not, and why that leaks data.
+    let rules = [
+        (
+            file_names,
+            project_folder,
+            "the project folder can still reveal file paths",
+        ),
+        (
+            project_names,
+            project_folder,
+            "the project folder usually contains the project name",
+        ),
+        (
+            project_names,
+            branch_names,
+            "branch names often reveal what the project is",
+        ),
+        (
+            file_names,
+            dependencies,
+            "dependencies can reveal what the files contain",
+        ),
+    ];
+    for ((set, set_on), (unset, unset_on), why) in rules {
+        if set_on && !unset_on {
+            report.push(Diagnostic::warning(
+                DiagnosticCode::PrivacyInconsistent,
+                format!("{set} is set but {unset} is not; {why}"),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::Level;
+
+    /// Run [`check_timeout`] on `timeout` and return the codes it produced.
+    fn timeout_codes(timeout: &str) -> Vec<&'static str> {
+        let mut report = Report::new();
+        check_timeout(timeout, &mut report);
+        report.iter().map(|d| d.code.as_str()).collect()
+    }
+
+    #[test]
+    fn patterns() {
+        let mut report = Report::new();
+        check_patterns(
+            "exclude",
+            "^COMMIT_EDITMSG$\n(unclosed\n.*\\.log$\n[z-a]",
+            &mut report,
+        );
+        let found = report
+            .iter()
+            .map(|d| (d.code.as_str(), d.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0].0, "pattern_invalid");
+        assert!(found[0].1.starts_with("Invalid exclude pattern on line 2:"));
+        assert!(found[1].1.starts_with("Invalid exclude pattern on line 4:"));
+        assert_eq!(found[2], ("patterns", "exclude has 2 valid pattern(s)"));
+    }
+
+    #[test]
+    fn bools() {
+        let settings = WakaSettings {
+            debug: "TRUE".to_string(),
+            hide_file_names: " yes ".to_string(),
+            no_ssl_verify: "0".to_string(),
+            ..Default::default()
+        };
+        let mut report = Report::new();
+        check_bools(&settings, &mut report);
+        assert_eq!(
+            report
+                .iter()
+                .map(|v| (v.code, v.message.as_str()))
+                .collect::<Vec<_>>(),
+            [(
+                DiagnosticCode::BoolInvalid,
+                "hide_file_names must be true or false, not \"yes\"; it is treated as false"
+            )]
+        );
+    }
+
+    #[test]
+    fn privacy() {
+        let settings = WakaSettings {
+            hide_file_names: "true".to_string(),
+            hide_project_folder: "1".to_string(),
+            ..Default::default()
+        };
+        let mut report = Report::new();
+        check_privacy(&settings, &mut report);
+        assert_eq!(
+            report
+                .iter()
+                .map(|v| v.message.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "hide_file_names is set but hide_dependencies is not; dependencies can reveal what \
+                 the files contain"
+            ]
+        );
+    }
+
+    #[test]
+    fn project_filters() {
+        let mut report = Report::new();
+        check_project_filter(DiagnosticCode::ExcludeUnknownProject, "", "x", &mut report);
+        check_project_filter(
+            DiagnosticCode::ExcludeUnknownProject,
+            "false",
+            "x",
+            &mut report,
+        );
+        check_project_filter(
+            DiagnosticCode::ExcludeUnknownProject,
+            "True",
+            "x",
+            &mut report,
+        );
+        check_project_filter(
+            DiagnosticCode::ExcludeUnknownProject,
+            " yes ",
+            "x",
+            &mut report,
+        );
+        assert_eq!(
+            report
+                .iter()
+                .map(|v| (v.level, v.message.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    Level::Info,
+                    "exclude_unknown_project is enabled: x, so some activity may be missing"
+                ),
+                (
+                    Level::Warning,
+                    "exclude_unknown_project must be true or false, not \"yes\""
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn status_bar() {
+        let mut report = Report::new();
+        check_status_bar(DiagnosticCode::StatusBarEnabled, "", "x", &mut report);
+        check_status_bar(DiagnosticCode::StatusBarEnabled, "true", "x", &mut report);
+        check_status_bar(DiagnosticCode::StatusBarEnabled, "0", "x", &mut report);
+        check_status_bar(DiagnosticCode::StatusBarEnabled, "off", "x", &mut report);
+        assert_eq!(
+            report
+                .iter()
+                .map(|v| (v.level, v.message.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    Level::Info,
+                    "status_bar_enabled is false: x, though time is still tracked"
+                ),
+                (
+                    Level::Warning,
+                    "status_bar_enabled must be true or false, not \"off\""
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn debug() {
+        let mut report = Report::new();
+        check_debug("", &mut report);
+        check_debug("/tmp/wakatime.log", &mut report);
+        let messages = report
+            .iter()
+            .map(|d| d.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                "debug mode is enabled; this writes verbose logs and may impact performance",
+                "debug mode is enabled; this writes verbose logs and may impact performance (logs go to /tmp/wakatime.log)"
+            ]
+        );
+    }
+
+    /// Run [`check_log_file`] on `log_file` and return the codes it produced.
+    fn log_file_codes(log_file: &Path) -> Vec<&'static str> {
+        let mut report = Report::new();
+        check_log_file(log_file.to_str().unwrap(), &mut report);
+        report.iter().map(|d| d.code.as_str()).collect()
+    }
+
+    #[test]
+    fn log_files() {
+        let dir = std::env::temp_dir().join(format!("wakadoctor-log-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let log_file = dir.join("wakatime.log");
+
+        assert_eq!(log_file_codes(&log_file), ["log_file"]);
+        assert!(!log_file.exists());
+        std::fs::write(&log_file, "existing\n").unwrap();
+        assert_eq!(log_file_codes(&log_file), ["log_file"]);
+        assert_eq!(std::fs::read_to_string(&log_file).unwrap(), "existing\n");
+
+        assert_eq!(
+            log_file_codes(&dir.join("missing").join("wakatime.log")),
+            ["log_file_dir_missing"]
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            std::fs::remove_file(&log_file).unwrap();
+            std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o500)).unwrap();
+            // Root can write anywhere, so only check when the directory really is read-only.
+            if std::fs::write(&log_file, "").is_err() {
+                assert_eq!(log_file_codes(&log_file), ["log_file_unwritable"]);
+            }
+            std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700)).unwrap();
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn hostnames() {
+        let codes = |hostname| {
+            let mut report = Report::new();
+            check_hostname(hostname, &mut report);
+            report.iter().map(|d| d.code.as_str()).collect::<Vec<_>>()
+        };
+        assert_eq!(codes("dev-box.local"), ["hostname"]);
+        assert_eq!(codes("  "), ["hostname_empty"]);
+        assert_eq!(codes("my laptop"), ["hostname_invalid"]);
+        assert_eq!(codes("box_1"), ["hostname_invalid"]);
+    }
+
+    #[test]
+    fn rate_limits() {
+        let messages = |rate_limit| {
+            let mut report = Report::new();
+            check_rate_limit(rate_limit, &mut report);
+            report
+                .into_iter()
+                .map(|d| (d.code.as_str(), d.message))
+                .collect::<Vec<_>>()
+        };
+        assert!(messages("").is_empty());
+        assert_eq!(messages("120")[0].0, "heartbeat_rate_limit");
+        assert_eq!(
+            messages("3600"),
+            [(
+                "heartbeat_rate_limit_extreme",
+                "heartbeat_rate_limit_seconds of 3600 means at most one heartbeat per hour"
+                    .to_string()
+            )]
+        );
+        assert!(messages("900")[0].1.ends_with("per 15 minutes"));
+        assert_eq!(messages("5")[0].0, "heartbeat_rate_limit_extreme");
+        assert_eq!(messages("0")[0].0, "heartbeat_rate_limit_extreme");
+        assert_eq!(messages("2m")[0].0, "heartbeat_rate_limit_invalid");
+        assert_eq!(messages("-1")[0].0, "heartbeat_rate_limit_invalid");
+    }
+
+    #[test]
+    fn timeout_values() {
+        assert_eq!(timeout_codes(""), Vec::<&str>::new());
+        assert_eq!(timeout_codes("30"), ["timeout"]);
+        assert_eq!(timeout_codes("0"), ["timeout_zero"]);
+        assert_eq!(timeout_codes("-5"), ["timeout_invalid"]);
+        assert_eq!(timeout_codes("foo"), ["timeout_invalid"]);
+    }
+}
@@ -0,0 +1,90 @@
+//! Finding out which TLS version the server negotiates.
+
+use std::time::Duration;
+
+use url::Url;
+
+use crate::{
+    checks::CheckOptions,
+    client::{self, TlsVersion},
+    config::WakaSettings,
+    diagnostic::{Diagnostic, DiagnosticCode, Report},
+};
+
+/// Diagnostic codes the TLS check can report.
+pub const CODES: &[DiagnosticCode] = &[
+    DiagnosticCode::TlsBelowMinimum,
+    DiagnosticCode::TlsOutdated,
+    DiagnosticCode::TlsVersion,
+];
+
+/// Whether a client limited to TLS versions from `min` up to `max` can connect to `url`.
+///
+/// Any response counts, whatever its status; only the handshake matters.
+async fn connects(
+    url: &Url,
+    settings: &WakaSettings,
+    opts: &CheckOptions,
+    timeout: Duration,
+    (min, max): (Option<TlsVersion>, Option<TlsVersion>),
+) -> bool {
+    // Problems with the settings are reported when building the real client.
+    let Some(builder) = client::client_builder(settings, opts, &mut Report::new()) else {
+        return false;
+    };
+    let Ok(client) = client::limit_tls(builder, min, max).build() else {
+        return false;
+    };
+    client::send(client.head(url.clone()).timeout(timeout), opts.verbose)
+        .await
+        .is_ok()
+}
+
+/// Report the TLS version the server at `url` negotiates, warning if it is older than TLS 1.2.
+///
+/// Clients pick the newest version both sides support, so the version is found by connecting
+/// with each version in turn, newest first. Nothing is reported if no connection succeeds, as the
+/// other network checks explain why.
+pub async fn check_tls(
+    url: &Url,
+    settings: &WakaSettings,
+    opts: &CheckOptions,
+    timeout: Duration,
+    report: &mut Report,
+) {
+    let probes = [
+        (TlsVersion::V1_3, (Some(TlsVersion::V1_3), None)),
+        (
+            TlsVersion::V1_2,
+            (Some(TlsVersion::V1_2), Some(TlsVersion::V1_2)),
+        ),
+        (TlsVersion::Legacy, (None, Some(TlsVersion::Legacy))),
+    ];
+    for (version, range) in probes {
+        if !connects(url, settings, opts, timeout, range).await {
+            continue;
+        }
+        if version < TlsVersion::V1_2 {
+            report.push(Diagnostic::warning(
+                DiagnosticCode::TlsOutdated,
+                format!(
+                    "Server only supports {version}, which is insecure and refused by many clients"
+                ),
+            ));
+        } else {
+            report.push(Diagnostic::info(
+                DiagnosticCode::TlsVersion,
+                format!("Connected using {version}"),
+            ));
+        }
+        if let Some(min) = opts.min_tls
+            && version < min
+        {
+            report.push(Diagnostic::warning(
+                DiagnosticCode::TlsBelowMinimum,
+                format!("Server does not support {min} or newer, required by --min-tls"),
+            ));
+        }
+        return;
+    }
+}
@@ -0,0 +1,317 @@
+//! Checking for a newer release of wakadoctor, with `--check-update`.
+
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    path::Path,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    client,
+    diagnostic::{Diagnostic, DiagnosticCode, Report},
+};
+
+/// Diagnostic codes the update check can report.
+pub const CODES: &[DiagnosticCode] =
+    &[DiagnosticCode::UpdateAvailable, DiagnosticCode::UpdateCheck];
+
+/// crates.io API endpoint describing the wakadoctor crate.
+pub const CRATES_IO_URL: &str = "https://crates.io/api/v1/crates/wakadoctor";
+
+/// How long a looked up latest version is reused before asking crates.io again.
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// A semver version of wakadoctor or the wakatime CLI, ordered by semver precedence. Build
+/// metadata is ignored.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Version {
+    /// Major version.
+    pub major: u64,
+    /// Minor version.
+    pub minor: u64,
+    /// Patch version.
+    pub patch: u64,
+    /// Prerelease identifiers, such as `beta` and `1` in `1.0.0-beta.1`; empty for releases.
+    pub pre: Vec<Prerelease>,
+}
+
+/// An identifier of a [`Version`] prerelease. Numeric identifiers sort before alphanumeric ones.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Prerelease {
+    /// An identifier of only digits, compared numerically.
+    Numeric(u64),
+    /// Any other identifier, compared in ASCII order.
+    Alphanumeric(String),
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid version \"{s}\", expected e.g. 1.2.3 or 1.2.3-beta.1");
+        let version = s.trim().split('+').next().unwrap_or_default();
+        let (core, pre) = match version.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (version, None),
+        };
+        let core = core
+            .split('.')
+            .map(|v| v.parse::<u64>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let [major, minor, patch] = core[..] else {
+            return Err(invalid());
+        };
+        let pre = match pre {
+            Some(pre) => pre
+                .split('.')
+                .map(|v| match v.parse() {
+                    Ok(v) => Ok(Prerelease::Numeric(v)),
+                    Err(_) if !v.is_empty() => Ok(Prerelease::Alphanumeric(v.to_string())),
+                    Err(_) => Err(invalid()),
+                })
+                .collect::<Result<_, _>>()?,
+            None => Vec::new(),
+        };
+        Ok(Self {
+            major,
+            minor,
+            patch,
+            pre,
+        })
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        for (i, v) in self.pre.iter().enumerate() {
+            f.write_str(if i == 0 { "-" } else { "." })?;
+            match v {
+                Prerelease::Numeric(v) => write!(f, "{v}")?,
+                Prerelease::Alphanumeric(v) => f.write_str(v)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            // A prerelease comes before the release itself.
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre.cmp(&other.pre),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The latest version cached in `cache`, written as `<unix time> <version>`, if it is younger
+/// than [`CACHE_TTL`] at `now`.
+fn cached_version(cache: &str, now: SystemTime) -> Option<Version> {
+    let (written, version) = cache.trim().split_once(' ')?;
+    let written = UNIX_EPOCH + Duration::from_secs(written.parse().ok()?);
+    if now.duration_since(written).ok()? > CACHE_TTL {
+        return None;
+    }
+    version.parse().ok()
+}
+
+/// Look up the latest stable version on crates.io at `url`.
+async fn fetch_latest(
+    url: &str,
+    client: &reqwest::Client,
+    timeout: Duration,
+    verbose: bool,
+) -> Result<Version, String> {
+    let response = client::send(client.get(url).timeout(timeout), verbose)
+        .await
+        .map_err(|e| format!("Cannot check for updates with error \"{e}\""))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "Cannot check for updates: crates.io answered {}",
+            response.status()
+        ));
+    }
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Cannot check for updates with error \"{e}\""))?;
+    let body = serde_json::from_str::<serde_json::Value>(&body)
+        .map_err(|e| format!("Cannot check for updates: crates.io sent invalid JSON ({e})"))?;
+    body.pointer("/crate/max_stable_version")
+        .and_then(|v| v.as_str())
+        .ok_or("Cannot check for updates: crates.io sent no version".to_string())?
+        .parse()
+}
+
+/// Report whether a wakadoctor release newer than this one is on crates.io at `url`.
+///
+/// The result is cached in `cache_file`, if given, for an hour.
+pub async fn check_update(
+    url: &str,
+    client: &reqwest::Client,
+    cache_file: Option<&Path>,
+    timeout: Duration,
+    verbose: bool,
+    report: &mut Report,
+) {
+    let now = SystemTime::now();
+    let cached = cache_file
+        .and_then(|v| std::fs::read_to_string(v).ok())
+        .and_then(|v| cached_version(&v, now));
+    let latest = match cached {
+        Some(v) => v,
+        None => match fetch_latest(url, client, timeout, verbose).await {
+            Ok(v) => {
+                if let Some(path) = cache_file {
+                    let written = now.duration_since(UNIX_EPOCH).unwrap_or_default();
+                    if let Some(dir) = path.parent() {
+                        let _ = std::fs::create_dir_all(dir);
+                    }
+                    // A missing cache only costs another request next time.
+                    let _ = std::fs::write(path, format!("{} {v}\n", written.as_secs()));
+                }
+                v
+            }
+            Err(e) => {
+                report.push(Diagnostic::warning(DiagnosticCode::UpdateCheck, e));
+                return;
+            }
+        },
+    };
+
+    compare_versions(env!("CARGO_PKG_VERSION"), &latest, report);
+}
+
+/// Report whether `latest` is newer than `current`, the version of this build.
+fn compare_versions(current: &str, latest: &Version, report: &mut Report) {
+    let current = match current.parse::<Version>() {
+        Ok(v) => v,
+        Err(e) => {
+            report.push(Diagnostic::warning(
+                DiagnosticCode::UpdateCheck,
+                format!("Cannot check for updates: this build has an {e}"),
+            ));
+            return;
+        }
+    };
+    if *latest > current {
+        report.push(Diagnostic::info(
+            DiagnosticCode::UpdateAvailable,
+            format!(
+                "wakadoctor {latest} is available (you have {current}); update with cargo install wakadoctor"
+            ),
+        ));
+    } else {
+        report.push(Diagnostic::ok(
+            DiagnosticCode::UpdateCheck,
+            format!("wakadoctor {current} is the latest version"),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_expires() {
+        let written = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let cache = "1700000000 9.1.0\n";
+        let version = "9.1.0".parse().ok();
+        assert_eq!(cached_version(cache, written), version);
+        assert_eq!(cached_version(cache, written + CACHE_TTL / 2), version);
+        assert_eq!(cached_version(cache, written + CACHE_TTL * 2), None);
+        assert_eq!(cached_version("garbage", written), None);
+    }
+
+    #[test]
+    fn versions() {
+        let versions = [
+            "0.9.9",
+            "1.0.0-1",
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0",
+            "1.1.0",
+        ]
+        .map(|v| v.parse::<Version>().unwrap());
+        assert!(versions.is_sorted_by(|a, b| a < b));
+        assert_eq!(versions[5].to_string(), "1.0.0-beta.2");
+        assert_eq!("1.0.0+build.5".parse::<Version>(), "1.0.0".parse());
+        for invalid in ["1.0", "1.0.0.0", "1.0.0-", "1.0.0-beta..1", "v1.0.0"] {
+            assert!(invalid.parse::<Version>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn compares_with_prerelease_build() {
+        let latest = "0.3.0".parse().unwrap();
+        let mut report = Report::new();
+        compare_versions("0.3.0-beta.1", &latest, &mut report);
+        compare_versions("0.3.0", &latest, &mut report);
+        compare_versions("garbage", &latest, &mut report);
+        assert_eq!(
+            report.iter().map(|d| d.code).collect::<Vec<_>>(),
+            [
+                DiagnosticCode::UpdateAvailable,
+                DiagnosticCode::UpdateCheck,
+                DiagnosticCode::UpdateCheck
+            ]
+        );
+        assert_eq!(report[2].level, crate::Level::Warning);
+    }
+
+    #[tokio::test]
+    async fn finds_newer_release() {
+        use wiremock::{Mock, MockServer, ResponseTemplate, matchers};
+
+        let server = MockServer::start().await;
+        Mock::given(matchers::method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"crate":{"name":"wakadoctor","max_stable_version":"99.0.0"}}"#,
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let cache = std::env::temp_dir().join(format!("wakadoctor-update-{}", std::process::id()));
+        let client = reqwest::Client::new();
+
+        for _ in 0..2 {
+            let mut report = Report::new();
+            check_update(
+                &server.uri(),
+                &client,
+                Some(&cache),
+                Duration::from_secs(5),
+                false,
+                &mut report,
+            )
+            .await;
+            assert_eq!(report[0].code, DiagnosticCode::UpdateAvailable);
+            assert!(
+                report[0]
+                    .message
+                    .starts_with("wakadoctor 99.0.0 is available")
+            );
+        }
+        // The second run used the cache.
+        server.verify().await;
+        std::fs::remove_file(cache).unwrap();
+    }
+}
@@ -0,0 +1,181 @@
+//! Checking the API key against the current user endpoint.
+
+use std::time::{Duration, Instant};
+
+use reqwest::StatusCode;
+use url::Url;
+
+use crate::{
+    checks::CheckOptions,
+    client,
+    diagnostic::{Diagnostic, DiagnosticCode, Report},
+    heartbeat::{self, endpoint},
+    host::WakaHost,
+};
+
+/// Diagnostic codes the user check can report.
+pub const CODES: &[DiagnosticCode] = &[
+    DiagnosticCode::UserOk,
+    DiagnosticCode::UserRejected,
+    DiagnosticCode::UserUnsupported,
+];
+
+/// Outcome of [`check_user`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UserCheck {
+    /// The server accepted the API key.
+    Authenticated,
+    /// The server rejected the API key.
+    Rejected,
+    /// The server is rate limiting requests.
+    RateLimited,
+    /// The server does not implement `users/current`.
+    Unsupported,
+    /// The request failed before the server answered.
+    Unreachable,
+    /// Something other than the server answered, such as a captive portal.
+    Intercepted,
+}
+
+/// Prefixes of tokens for other services that get pasted as API keys, with the service name.
+const FOREIGN_TOKEN_PREFIXES: &[(&str, &str)] = &[
+    ("github_pat_", "GitHub"),
+    ("ghp_", "GitHub"),
+    ("gho_", "GitHub"),
+    ("ghu_", "GitHub"),
+    ("ghs_", "GitHub"),
+    ("glpat-", "GitLab"),
+    ("xox", "Slack"),
+];
+
+/// Guidance for when the server rejects `api_key`, depending on what the key looks like.
+pub(crate) fn rejection_hint(api_key: &str) -> String {
+    if let Some((_, service)) = FOREIGN_TOKEN_PREFIXES
+        .iter()
+        .find(|(prefix, _)| api_key.starts_with(prefix))
+    {
+        return format!(
+            "This looks like a {service} token, not a wakatime API key; copy the key from your server's settings page instead."
+        );
+    }
+    let is_uuid = |v: &str| uuid::Uuid::parse_str(v).is_ok();
+    if is_uuid(api_key.strip_prefix("waka_").unwrap_or(api_key)) {
+        "The key is in a valid format, so it is probably wrong or revoked; copy it again from your server's settings page.".to_string()
+    } else {
+        "The key is not in any wakatime key format, so something else was probably pasted into api_key; copy the key from your server's settings page.".to_string()
+    }
+}
+
+/// Check `api_key` with `GET users/current`, which confirms the key without touching stats.
+pub async fn check_user(
+    url: &Url,
+    api_key: &str,
+    host: WakaHost,
+    client: &reqwest::Client,
+    timeout: Duration,
+    opts: &CheckOptions,
+    report: &mut Report,
+) -> UserCheck {
+    let start = Instant::now();
+    let response = match client::send(
+        client
+            .get(endpoint(url, "users/current"))
+            .bearer_auth(api_key)
+            .timeout(timeout),
+        opts.verbose,
+    )
+    .await
+    {
+        Ok(v) => v,
+        Err(e) => {
+            report.push(heartbeat::request_failed(&e, url, host, timeout, opts));
+            return UserCheck::Unreachable;
+        }
+    };
+    let latency = start.elapsed();
+    if let Some(date) = response.headers().get(reqwest::header::DATE)
+        && let Ok(date) = date.to_str()
+    {
+        heartbeat::check_clock_skew(date, time::UtcDateTime::now(), report);
+    }
+
+    let status = response.status();
+    if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+        report.push(Diagnostic::error(
+            DiagnosticCode::UserRejected,
+            format!(
+                "API key was rejected by the server ({status}). {host} is NOT configured correctly. {}",
+                rejection_hint(api_key)
+            ),
+        ));
+        return UserCheck::Rejected;
+    }
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        report.push(heartbeat::rate_limited(&response));
+        return UserCheck::RateLimited;
+    }
+    if !status.is_success() {
+        report.push(Diagnostic::info(
+            DiagnosticCode::UserUnsupported,
+            format!("Server does not support users/current ({status})"),
+        ));
+        return UserCheck::Unsupported;
+    }
+
+    if heartbeat::is_html(&response) {
+        report.push(heartbeat::captive_portal());
+        return UserCheck::Intercepted;
+    }
+
+    let body = serde_json::from_str(&response.text().await.unwrap_or_default())
+        .unwrap_or(serde_json::Value::Null);
+    match username(&body) {
+        Some(name) => report.push(Diagnostic::ok(
+            DiagnosticCode::UserOk,
+            format!("API key authenticated as user {name}"),
+        )),
+        None => report.push(Diagnostic::ok(
+            DiagnosticCode::UserOk,
+            "API key authenticated",
+        )),
+    }
+    heartbeat::report_latency("API request", latency, report);
+    UserCheck::Authenticated
+}
+
+/// Find the username in a `users/current` response.
+fn username(response: &serde_json::Value) -> Option<&str> {
+    ["/data/username", "/data/display_name"]
+        .iter()
+        .find_map(|pointer| response.pointer(pointer)?.as_str())
+        .filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejection_hints() {
+        assert!(rejection_hint("ghp_0123456789abcdef").contains("GitHub token"));
+        assert!(
+            rejection_hint("waka_3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e")
+                .contains("wrong or revoked")
+        );
+        assert!(
+            rejection_hint("3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e").contains("wrong or revoked")
+        );
+        assert!(rejection_hint("hunter2").contains("not in any wakatime key format"));
+    }
+
+    #[test]
+    fn username_from_response() {
+        let user =
+            serde_json::json!({ "data": { "username": "orpheus", "display_name": "Orpheus" } });
+        let anonymous =
+            serde_json::json!({ "data": { "username": null, "display_name": "Orpheus" } });
+        assert_eq!(username(&user), Some("orpheus"));
+        assert_eq!(username(&anonymous), Some("Orpheus"));
+        assert_eq!(username(&serde_json::json!({})), None);
+    }
+}
@@ -0,0 +1,115 @@
+//! Running `api_key_vault_cmd` to retrieve the API key.
+
+use std::{process::Stdio, time::Duration};
+
+/// How long the vault command may run before it is killed.
+pub const VAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Run `cmd` (split with shell-style quoting) and return its trimmed stdout.
+///
+/// On failure, returns a message describing what went wrong.
+pub async fn run_vault_cmd(cmd: &str, timeout: Duration) -> Result<String, String> {
+    let words = shell_words::split(cmd)
+        .map_err(|e| format!("Cannot parse vault command with error \"{e}\""))?;
+    let Some((program, args)) = words.split_first() else {
+        return Err("Vault command is empty".to_string());
+    };
+
+    let child = tokio::process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Cannot run vault command with error \"{e}\""))?;
+
+    let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(Ok(v)) => v,
+        Ok(Err(e)) => return Err(format!("Cannot run vault command with error \"{e}\"")),
+        Err(_) => {
+            return Err(format!(
+                "Vault command timed out after {} seconds",
+                timeout.as_secs()
+            ));
+        }
+    };
+
+    if !output.status.success() {
+        return Err(format!(
+            "Vault command failed with status {} (stderr: \"{}\")",
+            output
+                .status
+                .code()
+                .map(|v| v.to_string())
+                .unwrap_or("unknown (terminated by signal)".to_string()),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let key = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if key.is_empty() {
+        return Err("Vault command produced no output".to_string());
+    }
+    Ok(key)
+}
+
+/// Outcome of checking `api_key` against the output of `api_key_vault_cmd`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum DualKeyCheck {
+    /// `api_key` is not set, so only the vault command is used.
+    NotBoth,
+    /// Both are set and the vault command returned the plaintext key.
+    BothMatching,
+    /// Both are set and the vault command returned a different key.
+    BothDiffering,
+}
+
+/// Compare the plaintext `api_key` against `vault_key`, the key the vault command printed.
+pub fn check_dual_key(api_key: &str, vault_key: &str) -> DualKeyCheck {
+    if api_key.trim().is_empty() {
+        DualKeyCheck::NotBoth
+    } else if vault_key == api_key.trim() {
+        DualKeyCheck::BothMatching
+    } else {
+        DualKeyCheck::BothDiffering
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Key used by the tests.
+    const KEY: &str = "3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e";
+
+    #[test]
+    fn only_vault() {
+        assert_eq!(check_dual_key("", KEY), DualKeyCheck::NotBoth);
+    }
+
+    #[test]
+    fn both_matching() {
+        assert_eq!(
+            check_dual_key(&format!("  {KEY}  "), KEY),
+            DualKeyCheck::BothMatching
+        );
+    }
+
+    #[test]
+    fn both_differing() {
+        assert_eq!(
+            check_dual_key(KEY, "something-else"),
+            DualKeyCheck::BothDiffering
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn trims_vault_output() {
+        assert_eq!(
+            run_vault_cmd(&format!("echo '  {KEY}  '"), VAULT_TIMEOUT).await,
+            Ok(KEY.to_string())
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! Detecting [wakapi](https://github.com/muety/wakapi) servers.
+
+use std::time::Duration;
+
+use url::Url;
+
+use crate::{
+    client,
+    diagnostic::{Diagnostic, DiagnosticCode, Report},
+    fix::Fix,
+};
+
+/// Diagnostic codes wakapi detection can report.
+pub const CODES: &[DiagnosticCode] = &[
+    DiagnosticCode::Wakapi,
+    DiagnosticCode::PathOk,
+    DiagnosticCode::PathWrong,
+];
+
+/// API paths wakatime clients can use on wakapi, after the path wakapi is served under.
+const WAKAPI_API_PATHS: &[&str] = &["/api", "/api/compat/wakatime/v1"];
+
+/// Find the wakapi version in a `Server` header such as `wakapi/2.12.3`.
+fn server_version(server: &str) -> Option<&str> {
+    let rest = server.trim().strip_prefix("wakapi")?;
+    let version = rest.trim_start_matches(['/', ' ', 'v']);
+    (!version.is_empty()).then_some(version)
+}
+
+/// The path wakapi is served under, going by the API path `path`: everything before its last
+/// `api` segment, or all of it if there is none.
+fn base_path(path: &str) -> &str {
+    let path = path.trim_end_matches('/');
+    path.match_indices("/api")
+        .filter(|(i, v)| matches!(path[i + v.len()..].chars().next(), None | Some('/')))
+        .last()
+        .map_or(path, |(i, _)| &path[..i])
+}
+
+/// URL of the wakapi health endpoint on the server behind `url`, under the same base path.
+pub(crate) fn health_url(url: &Url) -> Url {
+    let mut health = url.clone();
+    health.set_path(&format!("{}/api/health", base_path(url.path())));
+    health.set_query(None);
+    health
+}
+
+/// Probe the server behind `url` for wakapi and, if found, check the API path, logging the probe
+/// if `verbose`.
+///
+/// Returns whether the server is wakapi.
+pub async fn detect(
+    url: &Url,
+    client: &reqwest::Client,
+    timeout: Duration,
+    verbose: bool,
+    report: &mut Report,
+) -> bool {
+    let Ok(response) = client::send(client.get(health_url(url)).timeout(timeout), verbose).await
+    else {
+        return false;
+    };
+
+    let server = response
+        .headers()
+        .get(reqwest::header::SERVER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_lowercase();
+    let version = server_version(&server).map(str::to_string);
+    let healthy = response.status().is_success()
+        && response.text().await.unwrap_or_default().contains("app=");
+    if version.is_none() && !healthy {
+        return false;
+    }
+
+    match version {
+        Some(version) => report.push(Diagnostic::ok(
+            DiagnosticCode::Wakapi,
+            format!("Detected wakapi server v{version}"),
+        )),
+        None => report.push(Diagnostic::ok(
+            DiagnosticCode::Wakapi,
+            "Detected wakapi server",
+        )),
+    }
+
+    let base = base_path(url.path());
+    if WAKAPI_API_PATHS
+        .iter()
+        .any(|v| url.path().trim_end_matches('/') == format!("{base}{v}"))
+    {
+        report.push(Diagnostic::ok(
+            DiagnosticCode::PathOk,
+            "wakapi API path is correct.",
+        ));
+    } else {
+        let expected = format!("{base}{}", WAKAPI_API_PATHS[0]);
+        report.push(
+            Diagnostic::error(
+                DiagnosticCode::PathWrong,
+                format!(
+                    "wakapi API path should be \"{expected}\", not \"{}\"",
+                    url.path()
+                ),
+            )
+            .with_fix(Fix::SetApiPath(expected)),
+        );
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn server_versions() {
+        assert_eq!(server_version("wakapi/2.12.3"), Some("2.12.3"));
+        assert_eq!(server_version("wakapi v2.0.0"), Some("2.0.0"));
+        assert_eq!(server_version("wakapi"), None);
+        assert_eq!(server_version("nginx/1.25"), None);
+    }
+
+    #[test]
+    fn health_urls() {
+        for (api_url, health) in [
+            ("https://my.server/api", "https://my.server/api/health"),
+            ("https://my.server/api/", "https://my.server/api/health"),
+            (
+                "https://my.server/api/compat/wakatime/v1",
+                "https://my.server/api/health",
+            ),
+            (
+                "https://my.server/wakapi/api",
+                "https://my.server/wakapi/api/health",
+            ),
+            (
+                "https://my.server/wakapi/api/compat/wakatime/v1",
+                "https://my.server/wakapi/api/health",
+            ),
+            (
+                "https://my.server/wakapi",
+                "https://my.server/wakapi/api/health",
+            ),
+            (
+                "https://my.server/apis/v1",
+                "https://my.server/apis/v1/api/health",
+            ),
+            ("https://my.server/", "https://my.server/api/health"),
+        ] {
+            assert_eq!(
+                health_url(&Url::parse(api_url).unwrap()).as_str(),
+                health,
+                "{api_url}"
+            );
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Detecting the wakatime CLI binary.
+
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticCode, Report},
+    update::Version,
+};
+
+/// Diagnostic codes the wakatime CLI check can report.
+pub const CODES: &[DiagnosticCode] = &[DiagnosticCode::CliMissing, DiagnosticCode::CliVersion];
+
+/// Names the wakatime CLI is installed under, newest first.
+const CLI_NAMES: &[&str] = &["wakatime-cli", "wakatime"];
+
+/// How long `--version` may take before it is killed.
+const VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Oldest wakatime CLI version not warned about by default.
+pub const MIN_CLI_VERSION: Version = Version {
+    major: 1,
+    minor: 73,
+    patch: 0,
+    pre: Vec::new(),
+};
+
+/// Find the first word of `--version` output that is a version, such as `v1.102.3` or
+/// `1.90.0-alpha`.
+fn parse_version_output(output: &str) -> Option<Version> {
+    output
+        .split_whitespace()
+        .find_map(|word| word.strip_prefix('v').unwrap_or(word).parse().ok())
+}
+
+/// Find the wakatime CLI, looking on `PATH` and then in `~/.wakatime/`.
+fn find_cli() -> Option<PathBuf> {
+    let exe = |name: &str| format!("{name}{}", std::env::consts::EXE_SUFFIX);
+
+    if let Some(path) = std::env::var_os("PATH") {
+        for name in CLI_NAMES {
+            for dir in std::env::split_paths(&path) {
+                let candidate = dir.join(exe(name));
+                if candidate.is_file() {
+                    return Some(candidate);
+                }
+            }
+        }
+    }
+
+    // Editor plugins download the CLI as e.g. `~/.wakatime/wakatime-cli-linux-amd64`.
+    let dir = dirs::home_dir()?.join(".wakatime");
+    let mut found = std::fs::read_dir(&dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .file_name()
+                    .and_then(|v| v.to_str())
+                    .is_some_and(|v| v.starts_with("wakatime-cli") && !v.ends_with(".zip"))
+        })
+        .collect::<Vec<_>>();
+    found.sort();
+    found.into_iter().next()
+}
+
+/// Run `path --version` and return its output.
+async fn cli_version_output(path: &Path) -> Option<String> {
+    let child = tokio::process::Command::new(path)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .ok()?;
+    let output = tokio::time::timeout(VERSION_TIMEOUT, child.wait_with_output())
+        .await
+        .ok()?
+        .ok()?;
+    // The legacy python CLI prints its version to stderr.
+    Some(
+        String::from_utf8_lossy(&output.stdout).into_owned()
+            + &String::from_utf8_lossy(&output.stderr),
+    )
+}
+
+/// Check the wakatime CLI is installed and at least `min_version`.
+pub async fn check_cli(min_version: &Version, report: &mut Report) {
+    let Some(path) = find_cli() else {
+        report.push(Diagnostic::warning(
+            DiagnosticCode::CliMissing,
+            "wakatime-cli not found; your editor may download it automatically",
+        ));
+        return;
+    };
+
+    let Some(version) = cli_version_output(&path)
+        .await
+        .as_deref()
+        .and_then(parse_version_output)
+    else {
+        report.push(Diagnostic::warning(
+            DiagnosticCode::CliVersion,
+            format!(
+                "Found wakatime-cli at {} but could not determine its version",
+                path.display()
+            ),
+        ));
+        return;
+    };
+
+    if version < *min_version {
+        report.push(Diagnostic::warning(
+            DiagnosticCode::CliVersion,
+            format!(
+                "wakatime-cli v{version} at {} is older than v{min_version}; consider updating",
+                path.display()
+            ),
+        ));
+    } else {
+        report.push(Diagnostic::ok(
+            DiagnosticCode::CliVersion,
+            format!("Found wakatime-cli v{version} ({})", path.display()),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_version_output() {
+        let parse = |output| parse_version_output(output).map(|v| v.to_string());
+        assert_eq!(parse("v1.102.3\n"), Some("1.102.3".to_string()));
+        assert_eq!(
+            parse("wakatime-cli v1.90.0-alpha"),
+            Some("1.90.0-alpha".to_string())
+        );
+        assert_eq!(parse("<local-build>"), None);
+        // Numbers that are not versions, such as in error messages, are skipped.
+        assert_eq!(parse("Error: 2 args"), None);
+        assert_eq!(parse("13.0 v1.2"), None);
+    }
+}
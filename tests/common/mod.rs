@@ -0,0 +1,46 @@
+//! Helpers shared by the integration tests.
+// Each test crate uses only some of these.
+#![allow(dead_code)]
+
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+/// An API key in the format Hackatime and custom servers expect.
+pub const API_KEY: &str = "3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e";
+
+/// A Hackatime config that passes every offline check.
+pub const CLEAN_CONFIG: &str = "[settings]\napi_url = https://hackatime.hackclub.com/api/hackatime/v1\napi_key = 3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e\n";
+
+/// A config with a custom host, which is warned about.
+pub const CUSTOM_CONFIG: &str = "[settings]\napi_url = https://waka.example.com/api/v1\napi_key = 3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e\n";
+
+/// Arguments keeping wakadoctor off the network and from looking for wakatime-cli.
+pub const OFFLINE: &[&str] = &["--offline", "--skip-cli-check"];
+
+/// Run wakadoctor with `args` and `config` piped through stdin.
+///
+/// `--config-location -` is appended to `args`, so they may start with a subcommand.
+pub fn run_with_stdin(config: &str, args: &[&str]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_wakadoctor"))
+        .args(args)
+        .args(["--config-location", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(config.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+/// [`run_with_stdin`] with [`OFFLINE`] before `args`.
+pub fn run_offline(config: &str, args: &[&str]) -> Output {
+    run_with_stdin(config, &[OFFLINE, args].concat())
+}
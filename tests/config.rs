@@ -0,0 +1,133 @@
+//! Problems with the structure of the config file.
+
+use std::process::Command;
+
+mod common;
+
+#[test]
+fn empty_config() {
+    let path = std::env::temp_dir().join(format!("wakadoctor-empty-{}.cfg", std::process::id()));
+    std::fs::write(&path, " \n\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_wakadoctor"))
+        .arg("--config-location")
+        .arg(&path)
+        .arg("--offline")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains(&format!(
+        "ℹ️ [config_path] - Reading config from: {}",
+        path.display()
+    )));
+    assert!(stdout.contains("❌ [config_empty] - Config file is empty"));
+    assert!(!stdout.contains("[config_parse]"));
+    assert!(!stdout.contains("[key_missing]"));
+    assert_eq!(output.status.code(), Some(1));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn sectionless_config() {
+    let output = common::run_with_stdin(
+        "api_key = 3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e\napi_url = https://hackatime.hackclub.com/api/hackatime/v1\n",
+        &["--offline"],
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains(
+        "❌ [config_no_settings] - Config has no [settings] section; keys must live under [settings] (found top-level keys: api_key, api_url)"
+    ));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn crlf_config() {
+    let output = common::run_offline(
+        "[settings]\r\napi_url = https://hackatime.hackclub.com/api/hackatime/v1\r\napi_key = 3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e\r\nexclude =\r\n    ^COMMIT_EDITMSG$\r\n    ^TAG_EDITMSG$\r\n",
+        &[],
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    if cfg!(unix) {
+        assert!(stdout.contains("[config_crlf]"));
+    }
+    assert!(stdout.contains("✅ [patterns] - exclude has 2 valid pattern(s)"));
+    assert!(stdout.contains("✅ [key_valid]"));
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn quoted_api_key() {
+    for key in [
+        "\"3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e\"",
+        "' 3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e '",
+    ] {
+        let output = common::run_offline(
+            &format!(
+                "[settings]\napi_url = https://hackatime.hackclub.com/api/hackatime/v1\napi_key = {key}\n"
+            ),
+            &[],
+        );
+        let stdout = String::from_utf8(output.stdout).unwrap();
+
+        assert!(stdout.contains(
+            "⚠️ [key_quoted] - API key has surrounding quotes/whitespace that may break authentication"
+        ));
+        assert!(stdout.contains("✅ [key_valid] - Hackatime API key is in valid format"));
+        assert_eq!(output.status.code(), Some(2));
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn vault_key_takes_precedence() {
+    let output = common::run_offline(
+        "[settings]\napi_url = https://hackatime.hackclub.com/api/hackatime/v1\napi_key = not-a-key\napi_key_vault_cmd = echo 3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e\n",
+        &[],
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains(
+        "⚠️ [vault_dual_key_mismatch] - Vault command output differs from api_key; api_key is ignored"
+    ));
+    assert!(stdout.contains("✅ [key_valid] - Hackatime API key is in valid format"));
+    assert!(!stdout.contains("[key_invalid]"));
+}
+
+#[test]
+fn non_utf8_config() {
+    let path = std::env::temp_dir().join(format!("wakadoctor-latin1-{}.cfg", std::process::id()));
+    let mut text = common::CLEAN_CONFIG.as_bytes().to_vec();
+    // `# café`, saved as Latin-1.
+    text.extend(b"# caf\xe9\n");
+    std::fs::write(&path, text).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_wakadoctor"))
+        .arg("--config-location")
+        .arg(&path)
+        .args(common::OFFLINE)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains(
+        "⚠️ [config_not_utf8] - Config file is not valid UTF-8; non-ASCII characters may be corrupted"
+    ));
+    assert!(stdout.contains("✅ [key_valid]"));
+    assert_eq!(output.status.code(), Some(2));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn indented_config() {
+    let output = common::run_offline(
+        "[settings]\n  api_url = https://hackatime.hackclub.com/api/hackatime/v1\n\tapi_key = 3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e\n",
+        &[],
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains(
+        "ℹ️ [config_indented] - Keys appear indented; INI keys must start at column 0 (indented: api_url, api_key)"
+    ));
+    assert_eq!(output.status.code(), Some(1));
+}
@@ -0,0 +1,45 @@
+//! `--dry-run`, which lists the network requests instead of making any.
+
+use wakadoctor::{CheckOptions, DiagnosticCode, config};
+use wiremock::{Mock, MockServer, ResponseTemplate, matchers};
+
+#[tokio::test]
+async fn lists_requests_without_sending_them() {
+    let server = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let config = config::parse_config(&format!(
+        "[settings]\napi_url = {}/api/v1\napi_key = 3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e\n",
+        server.uri()
+    ))
+    .unwrap();
+    let opts = CheckOptions {
+        dry_run: true,
+        send_real_heartbeat: true,
+        custom_server: true,
+        skip_cli_check: true,
+        ..Default::default()
+    };
+    let report = wakadoctor::run_checks(&config, &opts).await;
+
+    let planned = report
+        .iter()
+        .filter(|d| d.code == DiagnosticCode::DryRun)
+        .map(|d| d.message.as_str())
+        .collect::<Vec<_>>();
+    assert!(
+        planned.contains(
+            &format!(
+                "Would send GET {}/api/health to detect wakapi",
+                server.uri()
+            )
+            .as_str()
+        )
+    );
+    assert!(!report.iter().any(|d| d.code == DiagnosticCode::Offline));
+    server.verify().await;
+}
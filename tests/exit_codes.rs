@@ -0,0 +1,30 @@
+//! Process exit codes.
+
+mod common;
+
+#[test]
+fn clean_config() {
+    let output = common::run_offline(common::CLEAN_CONFIG, &[]);
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn broken_url() {
+    let output = common::run_offline(
+        "[settings]\napi_url = not a url\napi_key = 3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e\n",
+        &[],
+    );
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn warning_only_config() {
+    let output = common::run_offline(common::CUSTOM_CONFIG, &[]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn warning_only_config_strict() {
+    let output = common::run_offline(common::CUSTOM_CONFIG, &["--strict"]);
+    assert_eq!(output.status.code(), Some(1));
+}
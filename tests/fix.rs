@@ -0,0 +1,55 @@
+//! Tests of `--fix`.
+
+use std::process::Command;
+
+mod common;
+
+#[test]
+fn fixes_scheme_path_and_permissions_with_backup() {
+    let dir = std::env::temp_dir().join(format!("wakadoctor-fix-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(".wakatime.cfg");
+    let original = "[settings]\napi_url = http://hackatime.hackclub.com/api/v1\napi_key = 00000000-0000-0000-0000-000000000000\n";
+    std::fs::write(&path, original).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wakadoctor"))
+        .arg("--config-location")
+        .arg(&path)
+        .args(["--fix", "--yes"])
+        .args(common::OFFLINE)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "[settings]\napi_url = https://hackatime.hackclub.com/api/hackatime/v1\napi_key = 00000000-0000-0000-0000-000000000000\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join(".wakatime.cfg.bak")).unwrap(),
+        original
+    );
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        for file in [&path, &dir.join(".wakatime.cfg.bak")] {
+            let mode = std::fs::metadata(file).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wakadoctor"))
+        .arg("--config-location")
+        .arg(&path)
+        .args(common::OFFLINE)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
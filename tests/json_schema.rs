@@ -0,0 +1,56 @@
+//! The JSON Schema printed by `--json-schema`.
+
+use std::process::Command;
+
+use serde_json::Value;
+
+mod common;
+
+/// Names of the properties `schema` declares, following a `$ref` into `defs`.
+fn properties<'a>(schema: &'a Value, defs: &'a Value) -> Vec<&'a str> {
+    let schema = match schema["$ref"].as_str() {
+        Some(v) => &defs[v.trim_start_matches("#/$defs/")],
+        None => schema,
+    };
+    schema["properties"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect()
+}
+
+#[test]
+fn schema_matches_output() {
+    let output = Command::new(env!("CARGO_BIN_EXE_wakadoctor"))
+        .arg("--json-schema")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let schema: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let defs = &schema["$defs"];
+
+    let output = common::run_offline(common::CLEAN_CONFIG, &["--format", "json"]);
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    let top = properties(&schema, defs);
+    for key in report.as_object().unwrap().keys() {
+        assert!(top.contains(&key.as_str()), "{key} not in schema");
+    }
+    let summary = properties(&schema["properties"]["summary"], defs);
+    for key in report["summary"].as_object().unwrap().keys() {
+        assert!(
+            summary.contains(&key.as_str()),
+            "summary.{key} not in schema"
+        );
+    }
+    let diagnostic = properties(&schema["properties"]["diagnostics"]["items"], defs);
+    for d in report["diagnostics"].as_array().unwrap() {
+        for key in d.as_object().unwrap().keys() {
+            assert!(
+                diagnostic.contains(&key.as_str()),
+                "diagnostic {key} not in schema"
+            );
+        }
+    }
+}
@@ -0,0 +1,352 @@
+//! Heartbeats and API key checks against a mock server, covering each way the server can answer.
+
+use std::time::Duration;
+
+use url::Url;
+use wakadoctor::{
+    CheckOptions, DiagnosticCode, Report, Summary, WakaSettings, bulk, client, heartbeat,
+    host::WakaHost,
+    user::{self, UserCheck},
+    wakapi,
+};
+use wiremock::{Mock, MockServer, ResponseTemplate, matchers};
+
+/// API key sent to the mock server.
+const API_KEY: &str = "3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e";
+
+/// Timeout of requests to the mock server.
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Start a mock server answering heartbeats with `responses` in turn, the last one for every
+/// remaining request, and accepting the cleanup request.
+async fn heartbeat_server(responses: &[ResponseTemplate]) -> MockServer {
+    let server = MockServer::start().await;
+    let (last, first) = responses.split_last().unwrap();
+    for response in first {
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path("/api/v1/users/current/heartbeats"))
+            .respond_with(response.clone())
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+    }
+    Mock::given(matchers::method("POST"))
+        .and(matchers::path("/api/v1/users/current/heartbeats"))
+        .respond_with(last.clone())
+        .mount(&server)
+        .await;
+    Mock::given(matchers::method("DELETE"))
+        .and(matchers::path("/api/v1/users/current/heartbeats.bulk"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(&server)
+        .await;
+    server
+}
+
+/// Send a heartbeat to `server` with `opts` and return the report.
+async fn send_heartbeat(server: &MockServer, opts: &CheckOptions) -> Report {
+    let url = Url::parse(&format!("{}/api/v1", server.uri())).unwrap();
+    let mut report = Report::new();
+    let client = client::build_client(&WakaSettings::default(), opts, &mut report).unwrap();
+    heartbeat::send_heartbeat(
+        &url,
+        API_KEY,
+        WakaHost::Custom,
+        &client,
+        TIMEOUT,
+        opts,
+        &mut report,
+    )
+    .await;
+    report
+}
+
+/// Codes of the diagnostics in `report`, apart from the clock check every response with a `Date`
+/// header produces.
+fn codes(report: &Report) -> Vec<&'static str> {
+    report
+        .iter()
+        .map(|d| d.code.as_str())
+        .filter(|v| *v != "clock_skew")
+        .collect()
+}
+
+/// Message of the first diagnostic in `report` with `code`.
+fn message<'a>(report: &'a Report, code: &str) -> &'a str {
+    &report
+        .iter()
+        .find(|d| d.code.as_str() == code)
+        .unwrap()
+        .message
+}
+
+/// Exit code a run producing `report` would have.
+fn exit_code(report: &Report) -> u8 {
+    Summary::of(report).exit_code(false)
+}
+
+#[tokio::test]
+async fn accepted() {
+    let server =
+        heartbeat_server(&[ResponseTemplate::new(201).set_body_string(r#"{"data":{"id":"abc"}}"#)])
+            .await;
+    let report = send_heartbeat(&server, &CheckOptions::default()).await;
+
+    assert_eq!(
+        codes(&report),
+        [
+            "heartbeat_ok",
+            "heartbeat_latency",
+            "heartbeat_response",
+            "heartbeat_cleanup"
+        ]
+    );
+    assert_eq!(exit_code(&report), 0);
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(
+        requests.last().unwrap().body,
+        format!(
+            "{{\"date\":\"{}\",\"ids\":[\"abc\"]}}",
+            time::UtcDateTime::now().date()
+        )
+        .into_bytes()
+    );
+}
+
+#[tokio::test]
+async fn unauthorized() {
+    let server = heartbeat_server(&[ResponseTemplate::new(401)]).await;
+    let report = send_heartbeat(&server, &CheckOptions::default()).await;
+
+    assert_eq!(codes(&report), ["heartbeat_error"]);
+    assert!(message(&report, "heartbeat_error").contains("(401 Unauthorized)"));
+    assert!(message(&report, "heartbeat_error").contains("wrong or revoked"));
+    assert_eq!(exit_code(&report), 1);
+}
+
+#[tokio::test]
+async fn rate_limited() {
+    let server =
+        heartbeat_server(&[ResponseTemplate::new(429).insert_header("Retry-After", "30")]).await;
+    let report = send_heartbeat(&server, &CheckOptions::default()).await;
+
+    assert_eq!(codes(&report), ["rate_limited"]);
+    assert!(message(&report, "rate_limited").contains("(retry after 30s)"));
+    assert_eq!(exit_code(&report), 2);
+}
+
+#[tokio::test]
+async fn server_error_is_retried() {
+    let server = heartbeat_server(&[
+        ResponseTemplate::new(500),
+        ResponseTemplate::new(201).set_body_string(r#"{"data":{"id":"abc"}}"#),
+    ])
+    .await;
+    let opts = CheckOptions {
+        retries: 1,
+        ..Default::default()
+    };
+    let report = send_heartbeat(&server, &opts).await;
+
+    assert_eq!(codes(&report)[..2], ["heartbeat_retry", "heartbeat_ok"]);
+    assert_eq!(
+        message(&report, "heartbeat_retry"),
+        "Heartbeat attempt 1 failed (500 Internal Server Error), retrying..."
+    );
+    assert_eq!(exit_code(&report), 2);
+}
+
+#[tokio::test]
+async fn server_error_after_retries() {
+    let server = heartbeat_server(&[ResponseTemplate::new(500)]).await;
+    let opts = CheckOptions {
+        retries: 1,
+        ..Default::default()
+    };
+    let report = send_heartbeat(&server, &opts).await;
+
+    assert_eq!(codes(&report), ["heartbeat_retry", "heartbeat_error"]);
+    assert!(message(&report, "heartbeat_error").contains("(500 Internal Server Error)"));
+    assert_eq!(exit_code(&report), 1);
+}
+
+#[tokio::test]
+async fn captive_portal() {
+    let server = heartbeat_server(&[ResponseTemplate::new(200).set_body_raw(
+        "<html><body>Log in to WiFi</body></html>",
+        "text/html; charset=utf-8",
+    )])
+    .await;
+    let report = send_heartbeat(&server, &CheckOptions::default()).await;
+
+    assert_eq!(codes(&report), ["captive_portal"]);
+    assert_eq!(exit_code(&report), 2);
+}
+
+#[tokio::test]
+async fn timeout() {
+    let server = heartbeat_server(&[ResponseTemplate::new(201).set_delay(TIMEOUT * 3)]).await;
+    let report = send_heartbeat(&server, &CheckOptions::default()).await;
+
+    assert_eq!(codes(&report), ["heartbeat_timeout"]);
+    assert_eq!(exit_code(&report), 1);
+}
+
+#[tokio::test]
+async fn user_checks() {
+    let check = |response: ResponseTemplate| async move {
+        let server = MockServer::start().await;
+        Mock::given(matchers::method("GET"))
+            .and(matchers::path("/api/v1/users/current"))
+            .respond_with(response)
+            .mount(&server)
+            .await;
+        let url = Url::parse(&format!("{}/api/v1", server.uri())).unwrap();
+        let opts = CheckOptions::default();
+        let mut report = Report::new();
+        let client = client::build_client(&WakaSettings::default(), &opts, &mut report).unwrap();
+        let user = user::check_user(
+            &url,
+            API_KEY,
+            WakaHost::Custom,
+            &client,
+            TIMEOUT,
+            &opts,
+            &mut report,
+        )
+        .await;
+        (user, codes(&report))
+    };
+    let json =
+        |status| ResponseTemplate::new(status).set_body_string(r#"{"data":{"username":"alice"}}"#);
+
+    assert_eq!(
+        check(json(200)).await,
+        (
+            UserCheck::Authenticated,
+            vec!["user_ok", "heartbeat_latency"]
+        )
+    );
+    assert_eq!(
+        check(json(401)).await,
+        (UserCheck::Rejected, vec!["user_rejected"])
+    );
+    assert_eq!(
+        check(json(429)).await,
+        (UserCheck::RateLimited, vec!["rate_limited"])
+    );
+    assert_eq!(
+        check(json(404)).await,
+        (UserCheck::Unsupported, vec!["user_unsupported"])
+    );
+    assert_eq!(
+        check(ResponseTemplate::new(200).set_body_raw("<html></html>", "text/html")).await,
+        (UserCheck::Intercepted, vec!["captive_portal"])
+    );
+}
+
+#[tokio::test]
+async fn endpoint_probes() {
+    let check = |single: u16, bulk_status: u16| async move {
+        let server = MockServer::start().await;
+        for (path, status) in [
+            ("/api/v1/users/current/heartbeats", single),
+            ("/api/v1/users/current/heartbeats.bulk", bulk_status),
+        ] {
+            Mock::given(matchers::method("POST"))
+                .and(matchers::path(path))
+                .and(matchers::body_string("[]"))
+                .respond_with(ResponseTemplate::new(status))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+        let url = Url::parse(&format!("{}/api/v1", server.uri())).unwrap();
+        let mut report = Report::new();
+        let client = client::build_client(
+            &WakaSettings::default(),
+            &CheckOptions::default(),
+            &mut report,
+        )
+        .unwrap();
+        bulk::check_endpoints(&url, API_KEY, &client, TIMEOUT, false, &mut report).await;
+        server.verify().await;
+        codes(&report)
+    };
+
+    assert_eq!(check(400, 201).await, ["heartbeat_endpoints"]);
+    assert_eq!(check(202, 404).await, ["heartbeat_bulk_missing"]);
+    assert_eq!(check(405, 400).await, ["heartbeat_single_missing"]);
+    assert_eq!(check(404, 404).await, ["heartbeat_endpoints_missing"]);
+}
+
+#[tokio::test]
+async fn user_check_compares_clock() {
+    let server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/api/v1/users/current"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(r#"{"data":{}}"#)
+                .insert_header("Date", "Mon, 01 Jan 2001 00:00:00 GMT"),
+        )
+        .mount(&server)
+        .await;
+    let url = Url::parse(&format!("{}/api/v1", server.uri())).unwrap();
+    let opts = CheckOptions::default();
+    let mut report = Report::new();
+    let client = client::build_client(&WakaSettings::default(), &opts, &mut report).unwrap();
+    user::check_user(
+        &url,
+        API_KEY,
+        WakaHost::Custom,
+        &client,
+        TIMEOUT,
+        &opts,
+        &mut report,
+    )
+    .await;
+
+    assert!(message(&report, "clock_skew").starts_with("Your system clock is "));
+    assert!(
+        report
+            .iter()
+            .any(|d| d.code == DiagnosticCode::HeartbeatLatency && d.latency_ms.is_some())
+    );
+}
+
+#[tokio::test]
+async fn wakapi_under_subpath() {
+    let server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/wakapi/api/health"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("app=1\ndb=1"))
+        .mount(&server)
+        .await;
+    let client = client::build_client(
+        &WakaSettings::default(),
+        &CheckOptions::default(),
+        &mut Report::new(),
+    )
+    .unwrap();
+    let detect = |path: &'static str| {
+        let (server, client) = (&server, &client);
+        async move {
+            let url = Url::parse(&format!("{}{path}", server.uri())).unwrap();
+            let mut report = Report::new();
+            wakapi::detect(&url, client, TIMEOUT, false, &mut report).await;
+            report
+        }
+    };
+
+    for path in ["/wakapi/api", "/wakapi/api/compat/wakatime/v1"] {
+        assert_eq!(codes(&detect(path).await), ["wakapi", "path_ok"], "{path}");
+    }
+    let report = detect("/wakapi/api/v1").await;
+    assert_eq!(codes(&report), ["wakapi", "path_wrong"]);
+    assert_eq!(
+        report[1].fix,
+        Some(wakadoctor::fix::Fix::SetApiPath("/wakapi/api".to_string()))
+    );
+}
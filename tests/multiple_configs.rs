@@ -0,0 +1,93 @@
+//! Checking several configs in one run by repeating `--config-location`.
+
+use std::process::Command;
+
+mod common;
+
+#[test]
+fn checks_each_config() {
+    let dir = std::env::temp_dir().join(format!("wakadoctor-multiple-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let good = dir.join("good.cfg");
+    let empty = dir.join("empty.cfg");
+    std::fs::write(&good, common::CLEAN_CONFIG).unwrap();
+    std::fs::write(&empty, "").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_wakadoctor"))
+        .arg("--config-location")
+        .arg(&good)
+        .arg("--config-location")
+        .arg(&empty)
+        .args(common::OFFLINE)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    let good_section = stdout.find(&format!("== {} ==", good.display())).unwrap();
+    let empty_section = stdout.find(&format!("== {} ==", empty.display())).unwrap();
+    assert!(good_section < empty_section);
+    assert!(stdout[good_section..empty_section].contains("Summary: "));
+    assert!(stdout[empty_section..].contains("[config_empty]"));
+    assert!(stdout.contains("Overall (2 configs): Summary: "));
+    assert_eq!(
+        stdout
+            .matches("Wakadoctor - Test your wakatime configuration")
+            .count(),
+        1
+    );
+    assert_eq!(output.status.code(), Some(1));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn quiet_skips_configs_without_problems() {
+    let dir =
+        std::env::temp_dir().join(format!("wakadoctor-multiple-quiet-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let good = dir.join("good.cfg");
+    let empty = dir.join("empty.cfg");
+    std::fs::write(&good, common::CLEAN_CONFIG).unwrap();
+    std::fs::write(&empty, "").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&good, std::fs::Permissions::from_mode(0o600)).unwrap();
+    }
+    let output = Command::new(env!("CARGO_BIN_EXE_wakadoctor"))
+        .arg("--config-location")
+        .arg(&good)
+        .arg("--config-location")
+        .arg(&empty)
+        .args(common::OFFLINE)
+        .args(["--quiet"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(
+        !stdout.contains(&format!("== {} ==", good.display())),
+        "{stdout}"
+    );
+    assert!(
+        stdout.starts_with(&format!("== {} ==", empty.display())),
+        "{stdout}"
+    );
+    assert!(stdout.contains("[config_empty]"));
+    assert_eq!(output.status.code(), Some(1));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn rejects_other_formats() {
+    let output = Command::new(env!("CARGO_BIN_EXE_wakadoctor"))
+        .args(["--config-location", "a.cfg", "--config-location", "b.cfg"])
+        .args(["--format", "json"])
+        .output()
+        .unwrap();
+
+    assert!(
+        String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("only supports the human format")
+    );
+    assert_eq!(output.status.code(), Some(1));
+}
@@ -0,0 +1,39 @@
+//! `--offline`, which must not touch the network at all.
+
+use wakadoctor::{CheckOptions, DiagnosticCode, config};
+use wiremock::{Mock, MockServer, ResponseTemplate, matchers};
+
+#[tokio::test]
+async fn sends_no_requests() {
+    let server = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let config = config::parse_config(&format!(
+        "[settings]\napi_url = {}/api/v1\napi_key = 3f1c3c3e-1b1a-4c1e-9c1e-1b1a4c1e9c1e\n",
+        server.uri()
+    ))
+    .unwrap();
+    let opts = CheckOptions {
+        offline: true,
+        custom_server: true,
+        skip_cli_check: true,
+        ..Default::default()
+    };
+    let report = wakadoctor::run_checks(&config, &opts).await;
+
+    let offline = report
+        .iter()
+        .filter(|d| d.code == DiagnosticCode::Offline)
+        .count();
+    assert_eq!(offline, 1);
+    assert!(
+        !report
+            .iter()
+            .any(|d| d.code == DiagnosticCode::Dns || d.code == DiagnosticCode::HeartbeatSkipped)
+    );
+    server.verify().await;
+}
@@ -0,0 +1,117 @@
+//! Output formats other than the default, and options changing the human output.
+
+use std::process::Command;
+
+mod common;
+
+#[test]
+fn tap_numbers_checks_and_ends_with_plan() {
+    let output = common::run_offline(common::CUSTOM_CONFIG, &["--format", "tap"]);
+    assert_eq!(output.status.code(), Some(2));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines = stdout.lines().collect::<Vec<_>>();
+
+    assert_eq!(lines[0], "TAP version 13");
+    let tests = &lines[1..lines.len() - 1];
+    assert_eq!(*lines.last().unwrap(), format!("1..{}", tests.len()));
+    for (i, line) in tests.iter().enumerate() {
+        let number = format!(" {} - [", i + 1);
+        assert!(line.starts_with("ok ") || line.starts_with("not ok "));
+        assert!(line.contains(&number), "{line}");
+    }
+    assert!(tests.iter().any(|v| v.starts_with("not ok ")
+        && v.contains("[host_custom] Wakatime API host is custom server host")
+        && v.ends_with(" # TODO warning")));
+}
+
+#[test]
+fn junit_one_testcase_per_check() {
+    let output = common::run_offline(
+        &format!("{}timeout = <1&\n", common::CUSTOM_CONFIG),
+        &["--format", "junit"],
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.starts_with(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuite name=\"wakadoctor\" "
+    ));
+    assert!(stdout.ends_with("</testsuite>\n"));
+    let testcases = stdout.matches("<testcase ").count();
+    assert!(stdout.contains(&format!(" tests=\"{testcases}\" ")));
+    assert!(stdout.contains(
+        "<testcase classname=\"wakadoctor\" name=\"host_custom\">\n    <skipped message=\"Wakatime API host is custom server host or invalid host (psst- disable this warning with --custom-server)\"/>"
+    ));
+    assert!(stdout.contains(
+        "<failure message=\"timeout must be a positive integer, not &quot;&lt;1&amp;&quot;\">"
+    ));
+}
+
+#[test]
+fn sarif_one_result_per_diagnostic() {
+    let path = std::env::temp_dir().join(format!("wakadoctor-sarif-{}.cfg", std::process::id()));
+    std::fs::write(&path, format!("{}timeout = x\n", common::CUSTOM_CONFIG)).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_wakadoctor"))
+        .arg("--config-location")
+        .arg(&path)
+        .args(common::OFFLINE)
+        .args(["--format", "sarif"])
+        .output()
+        .unwrap();
+    let sarif: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert_eq!(sarif["version"], "2.1.0");
+    let run = &sarif["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], "wakadoctor");
+    let results = run["results"].as_array().unwrap();
+    let result = |rule: &str| results.iter().find(|v| v["ruleId"] == rule).unwrap();
+    assert_eq!(result("config_read")["kind"], "pass");
+    assert_eq!(result("config_read")["level"], "none");
+    assert_eq!(result("config_path")["kind"], "informational");
+    assert_eq!(result("config_path")["level"], "none");
+    assert_eq!(result("host_custom")["level"], "warning");
+    assert_eq!(result("timeout_invalid")["level"], "error");
+    assert_eq!(
+        result("timeout_invalid")["message"]["text"],
+        "timeout must be a positive integer, not \"x\""
+    );
+    assert_eq!(
+        result("timeout_invalid")["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+        url::Url::from_file_path(&path).unwrap().as_str()
+    );
+    let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+    assert!(
+        rules.iter().any(
+            |v| v["id"] == "timeout_invalid" && v["fullDescription"]["text"].as_str().is_some()
+        )
+    );
+    assert_eq!(output.status.code(), Some(1));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn quiet_prints_nothing_when_clean() {
+    let output = common::run_offline(common::CLEAN_CONFIG, &["--quiet"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn quiet_prints_only_problems() {
+    let output = common::run_offline(common::CUSTOM_CONFIG, &["--quiet"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("⚠️ [host_custom] - Wakatime API host is custom server host"));
+    assert!(!stdout.contains("✅"));
+    assert!(!stdout.contains("ℹ️"));
+    assert!(stdout.contains("Summary: "));
+}
+
+#[test]
+fn no_emoji_uses_ascii_labels() {
+    let output = common::run_offline(common::CUSTOM_CONFIG, &["--no-emoji"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("[WARN] [host_custom] - Wakatime API host is custom server host"));
+    assert!(stdout.contains("[OK] [config_read] - "));
+    assert!(!stdout.contains("⚠️"));
+}
@@ -0,0 +1,40 @@
+//! Tests of `--output`.
+
+mod common;
+
+#[test]
+fn writes_report_to_file() {
+    let dir = std::env::temp_dir().join(format!("wakadoctor-output-{}", std::process::id()));
+    let path = dir.join("reports").join("report.json");
+    let output = common::run_offline(
+        common::CLEAN_CONFIG,
+        &["--format", "json", "--output", path.to_str().unwrap()],
+    );
+    assert_eq!(output.status.code(), Some(0));
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("Summary: "));
+    assert!(stdout.contains(&format!("Report written to {}", path.display())));
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(report["summary"]["ok"], true);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn reports_write_errors() {
+    let file = std::env::temp_dir().join(format!("wakadoctor-output-{}.txt", std::process::id()));
+    std::fs::write(&file, "").unwrap();
+    // A regular file cannot be a directory, so the report cannot be written below it.
+    let path = file.join("report.txt");
+    let output = common::run_offline(common::CLEAN_CONFIG, &["--output", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        String::from_utf8(output.stderr)
+            .unwrap()
+            .starts_with("Cannot write report to ")
+    );
+
+    std::fs::remove_file(file).unwrap();
+}
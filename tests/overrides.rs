@@ -0,0 +1,32 @@
+//! Overriding config values from the command line.
+
+mod common;
+
+#[test]
+fn api_key_override() {
+    let output = common::run_offline(
+        "[settings]\napi_url = https://hackatime.hackclub.com/api/hackatime/v1\napi_key = not-a-key\napi_key_vault_cmd = false\n",
+        &["--api-key", "00000000-0000-0000-0000-000000000000"],
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("ℹ️ [key_override] - Using API key from command line"));
+    assert!(stdout.contains("✅ [key_valid] - Hackatime API key is in valid format"));
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn api_url_override() {
+    let output = common::run_offline(
+        "[settings]\napi_url = http://example.com\napi_key = 00000000-0000-0000-0000-000000000000\n",
+        &[
+            "--api-url",
+            "https://hackatime.hackclub.com/api/hackatime/v1",
+        ],
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("ℹ️ [api_url_override] - Using API URL from command line"));
+    assert!(stdout.contains("✅ [host_hackatime] - Wakatime API host is Hackatime host"));
+    assert_eq!(output.status.code(), Some(0));
+}
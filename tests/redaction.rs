@@ -0,0 +1,63 @@
+//! Keeping the API key out of output.
+
+use wiremock::{Mock, MockServer, ResponseTemplate, matchers};
+
+mod common;
+
+/// Run wakadoctor with `config` and `args` against `server`, whose URI replaces `{server}` in
+/// `config`, returning stdout and stderr.
+async fn run(server: &MockServer, config: &str, args: &[&'static str]) -> (String, String) {
+    let config = config.replace("{server}", &server.uri());
+    let args = args.to_vec();
+    let output = tokio::task::spawn_blocking(move || common::run_with_stdin(&config, &args))
+        .await
+        .unwrap();
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn key_never_printed() {
+    let server = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"status":"ok"}"#))
+        .mount(&server)
+        .await;
+
+    let key = common::API_KEY;
+    let config = format!("[settings]\napi_url = {{server}}/api\napi_key = {key}\n");
+    for format in ["human", "json"] {
+        let (stdout, stderr) = run(
+            &server,
+            &config,
+            &["--skip-cli-check", "-u", "--verbose", "--format", format],
+        )
+        .await;
+
+        assert!(stderr.contains("> GET"), "{stderr}");
+        assert!(!stdout.contains(key), "{stdout}");
+        assert!(!stderr.contains(key), "{stderr}");
+    }
+    assert!(!server.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn short_key_scrubbed() {
+    let server = MockServer::start().await;
+    // The vault command fails with the key in its stderr, which ends up in the failure message.
+    let config = "[settings]\napi_url = {server}/api/v1\napi_key = hunter2\napi_key_vault_cmd = sh -c \"echo hunter2 >&2; exit 1\"\n";
+    for format in ["human", "json"] {
+        let (stdout, _) = run(
+            &server,
+            config,
+            &["--skip-cli-check", "-u", "--format", format],
+        )
+        .await;
+
+        assert!(stdout.contains("vault_failed"), "{stdout}");
+        assert!(stdout.contains("****"), "{stdout}");
+        assert!(!stdout.contains("hunter2"), "{stdout}");
+    }
+}
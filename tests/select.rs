@@ -0,0 +1,60 @@
+//! Selecting diagnostics with `--only` and `--skip`.
+
+mod common;
+
+/// Codes in the human output `stdout`.
+fn codes(stdout: &[u8]) -> Vec<String> {
+    String::from_utf8(stdout.to_vec())
+        .unwrap()
+        .lines()
+        .filter_map(|v| Some(v.split_once(" [")?.1.split_once(']')?.0.to_string()))
+        .collect()
+}
+
+#[test]
+fn only_selected_codes() {
+    let output = common::run_offline(common::CUSTOM_CONFIG, &["--only", "host,scheme_https"]);
+    assert_eq!(codes(&output.stdout), ["scheme_https", "host_custom"]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn skipped_codes() {
+    let output = common::run_offline(common::CUSTOM_CONFIG, &["--skip", "host_custom"]);
+    let codes = codes(&output.stdout);
+    assert!(!codes.contains(&"host_custom".to_string()));
+    assert!(codes.contains(&"scheme_https".to_string()));
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn rejects_unknown_codes() {
+    let output = common::run_with_stdin(
+        common::CUSTOM_CONFIG,
+        &["--offline", "--skip", "cli_verison"],
+    );
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("unknown diagnostic code \"cli_verison\"")
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn skipped_probes_not_run() {
+    let server = wiremock::MockServer::start().await;
+    let config = format!(
+        "[settings]\napi_url = {}/api\napi_key = {}\n",
+        server.uri(),
+        common::API_KEY
+    );
+    // Over plain HTTP only wakapi detection talks to the server.
+    let output = tokio::task::spawn_blocking(move || {
+        common::run_with_stdin(&config, &["--skip-cli-check", "--skip", "wakapi,path"])
+    })
+    .await
+    .unwrap();
+    assert!(!codes(&output.stdout).contains(&"wakapi".to_string()));
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
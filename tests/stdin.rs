@@ -0,0 +1,15 @@
+//! Reading the config from stdin with `--config-location -`.
+
+mod common;
+
+#[test]
+fn parses_config_from_stdin() {
+    let output = common::run_with_stdin(
+        "[settings]\napi_url = https://hackatime.hackclub.com/api/hackatime/v1\n",
+        &["--offline"],
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("✅ [config_read] - Read Wakatime config from stdin"));
+    assert!(stdout.contains("✅ [config_parse] - Successfully parsed Wakatime config"));
+}
@@ -0,0 +1,45 @@
+//! Tests of the subcommands.
+
+use std::process::Command;
+
+mod common;
+
+#[test]
+fn check_is_the_default() {
+    let config = "[settings]\napi_url = https://api.wakatime.com/api/v1\napi_key = waka_00000000-0000-0000-0000-000000000000\n";
+    let args = [common::OFFLINE, &["-w"]].concat();
+    let default = common::run_with_stdin(config, &args);
+
+    let check = common::run_with_stdin(config, &[&["check"][..], &args].concat());
+
+    assert_eq!(default.status.code(), Some(0));
+    assert_eq!(default.stdout, check.stdout);
+}
+
+#[test]
+fn generates_completions_and_man_page() {
+    for args in [&["completions", "bash"][..], &["man"]] {
+        let output = Command::new(env!("CARGO_BIN_EXE_wakadoctor"))
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stdout).contains("wakadoctor"));
+    }
+}
+
+#[test]
+fn explains_codes() {
+    let explain = |code| {
+        Command::new(env!("CARGO_BIN_EXE_wakadoctor"))
+            .args(["explain", code])
+            .output()
+            .unwrap()
+    };
+
+    let output = explain("api_url_invalid");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("[api_url_invalid]\n\n"));
+
+    assert!(!explain("no_such_code").status.success());
+}
@@ -0,0 +1,43 @@
+//! The User-Agent of outgoing requests.
+
+use wakadoctor::{CheckOptions, Report, WakaSettings, client};
+use wiremock::{Mock, MockServer, ResponseTemplate, matchers};
+
+/// Send a request through a client built with `opts` and return the User-Agent the server saw.
+async fn seen_user_agent(opts: &CheckOptions) -> String {
+    let server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    let mut report = Report::new();
+    let client = client::build_client(&WakaSettings::default(), opts, &mut report).unwrap();
+    client.get(server.uri()).send().await.unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    requests[0].headers["user-agent"]
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+#[tokio::test]
+async fn mimics_wakatime_cli() {
+    let user_agent = seen_user_agent(&CheckOptions::default()).await;
+    assert!(user_agent.starts_with("wakatime/v"), "{user_agent}");
+    assert!(
+        user_agent.ends_with(concat!(" wakadoctor/", env!("CARGO_PKG_VERSION"))),
+        "{user_agent}"
+    );
+    assert_eq!(user_agent, client::default_user_agent());
+}
+
+#[tokio::test]
+async fn can_be_overridden() {
+    let opts = CheckOptions {
+        user_agent: Some("custom/1.0".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(seen_user_agent(&opts).await, "custom/1.0");
+}
@@ -0,0 +1,33 @@
+//! Logging HTTP traffic with `--verbose`.
+
+use wiremock::{Mock, MockServer, ResponseTemplate, matchers};
+
+mod common;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn logs_requests_and_responses() {
+    let server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .respond_with(ResponseTemplate::new(200).insert_header("x-test", "yes"))
+        .mount(&server)
+        .await;
+
+    let config = format!(
+        "[settings]\napi_url = {}/api\napi_key = {}\n",
+        server.uri(),
+        common::API_KEY
+    );
+    let output = tokio::task::spawn_blocking(move || {
+        common::run_with_stdin(&config, &["--skip-cli-check", "-u", "--verbose"])
+    })
+    .await
+    .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(
+        stderr.contains(&format!("> GET {}/api/health", server.uri())),
+        "{stderr}"
+    );
+    assert!(stderr.contains("< HTTP/1.1 200 OK"), "{stderr}");
+    assert!(stderr.contains("< x-test: yes"), "{stderr}");
+}